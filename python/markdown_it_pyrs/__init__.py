@@ -2,4 +2,71 @@
 
 from .markdown_it_pyrs import *  # noqa: F403
 
-__all__ = ("MarkdownIt", "Node", "__version__")  # noqa: F405
+__all__ = (
+    "Abbreviation",
+    "AbbreviationDefinition",
+    "Alert",
+    "Autolink",
+    "Blockquote",
+    "BulletList",
+    "CodeBlock",
+    "CodeInline",
+    "Container",
+    "Definition",
+    "DefinitionDescription",
+    "DefinitionList",
+    "DefinitionTerm",
+    "Em",
+    "Emoji",
+    "EventStream",
+    "Fence",
+    "FootnoteContainer",
+    "FootnoteDef",
+    "FootnoteInline",
+    "FootnoteRef",
+    "FootnoteRefAnchor",
+    "FrontMatter",
+    "Hardbreak",
+    "Heading",
+    "HeadingAnchor",
+    "Hr",
+    "HtmlBlock",
+    "HtmlInline",
+    "Image",
+    "Inserted",
+    "LHeading",
+    "Link",
+    "Linkify",
+    "ListItem",
+    "Mark",
+    "MarkdownIt",
+    "MathBlock",
+    "MathInline",
+    "Node",
+    "NodeTransformer",
+    "NodeVisitor",
+    "NodeWalker",
+    "OrderedList",
+    "Paragraph",
+    "Prose",
+    "Root",
+    "Softbreak",
+    "Strikethrough",
+    "Strong",
+    "Subscript",
+    "Superscript",
+    "Table",
+    "TableOfContents",
+    "TableBody",
+    "TableCell",
+    "TableHead",
+    "TableRow",
+    "Text",
+    "TextSpecial",
+    "TodoCheckbox",
+    "TreeEdit",
+    "Wikilink",
+    "__version__",
+    "diff_trees",
+    "node_schema",
+)  # noqa: F405
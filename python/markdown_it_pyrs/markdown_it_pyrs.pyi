@@ -1,9 +1,30 @@
-from typing import Any, Iterable, List, Literal, Mapping, Optional, Sequence, Tuple
+from typing import (
+    Any,
+    Callable,
+    ClassVar,
+    Dict,
+    Iterable,
+    Iterator,
+    List,
+    Literal,
+    Mapping,
+    Optional,
+    Sequence,
+    Tuple,
+    TypedDict,
+    Union,
+)
 
 __version__: str
 
 class Node:
-    """Single node in the Markdown AST tree."""
+    """Single node in the Markdown AST tree.
+
+    Nodes can be pickled, including all of their descendants (but not their parent).
+
+    Nodes of known types are created as instances of a subclass (e.g. `Heading`),
+    which exposes the entries of `meta` as typed properties.
+    """
 
     _rust_path: Optional[str]
     """The rust module path of the node type"""
@@ -14,6 +35,10 @@ class Node:
     children: Sequence["Node"]
     """The children of the node"""
 
+    @property
+    def parent(self) -> Optional["Node"]:
+        """The parent of the node, or None if it is the root of a tree."""
+
     srcmap: Optional[Tuple[int, int]]
     """Byte offset mapping of the (start, end) of the source syntax."""
 
@@ -35,6 +60,92 @@ class Node:
     def __str__(self) -> str:
         """Return a string representation of the node."""
 
+    def __eq__(self, other: object) -> bool:
+        """Structurally compare this node (and its descendants) with another node."""
+
+    def __ne__(self, other: object) -> bool:
+        """Structurally compare this node (and its descendants) with another node."""
+
+    # nodes are mutable and compared structurally, so they are not hashable
+    __hash__: ClassVar[None]  # type: ignore[assignment]
+
+    def equals(self, other: "Node", *, srcmap: bool = True) -> bool:
+        """Structurally compare this node (and its descendants) with another node.
+
+        The name, attrs, meta and children of nodes are compared,
+        and also the srcmap, if `srcmap` is True.
+        Note, `node1 == node2` is equivalent to `node1.equals(node2, srcmap=True)`.
+        """
+
+    def __copy__(self) -> "Node":
+        """Return a deep copy of the node (without a parent), the same as `__deepcopy__`.
+
+        Children are not shared with the copy, since a node can only have a single parent.
+        """
+
+    def __deepcopy__(self, memo: Mapping[int, Any]) -> "Node":
+        """Return a deep copy of the node (without a parent)."""
+
+    def to_bytes(self) -> bytes:
+        """Serialize the node and its descendants to a compact binary format.
+
+        Meta values must be None, bool, int, float, str, bytes,
+        or lists, tuples and dicts of these.
+
+        :raises TypeError: If a meta value cannot be serialized.
+        :raises ValueError: If a meta value is nested too deeply, or is recursive.
+        """
+
+    @staticmethod
+    def from_bytes(data: bytes) -> "Node":
+        """Deserialize a node and its descendants, from the output of `to_bytes`.
+
+        :raises ValueError: If the data is invalid.
+        """
+
+    def append(self, node: "Node") -> None:
+        """Append a node to the end of the children of this node.
+
+        If the node already has a parent, it is first removed from it.
+
+        :param node: The node to append.
+        """
+
+    def insert(self, index: int, node: "Node") -> None:
+        """Insert a node into the children of this node, before `index`.
+
+        If the node already has a parent, it is first removed from it.
+
+        :param index: The index to insert the node before.
+        :param node: The node to insert.
+        """
+
+    def remove(self) -> None:
+        """Remove this node from its parent.
+
+        :raises ValueError: If the node has no parent.
+        """
+
+    def replace_with(self, nodes: Union["Node", Sequence["Node"]]) -> None:
+        """Replace this node, within its parent, by one or more nodes.
+
+        :param nodes: The node(s) to replace this node with.
+        :raises ValueError: If the node has no parent.
+        """
+
+    def wrap(self, name: str) -> "Node":
+        """Wrap this node in a new node, which takes its place within the parent.
+
+        :param name: The type of the new node.
+        :returns: The new (wrapping) node.
+        """
+
+    def unwrap(self) -> None:
+        """Remove this node from its parent, moving its children into its place.
+
+        :raises ValueError: If the node has no parent.
+        """
+
     def walk(self, *, include_self: bool = True) -> Iterable["Node"]:
         """Recursively yield all descendant nodes in the tree.
 
@@ -44,6 +155,83 @@ class Node:
         :param include_self: whether to include self in the output
         """
 
+    def iter_walk(
+        self, *, include_self: bool = True, order: Literal["pre", "post"] = "pre"
+    ) -> "NodeWalker":
+        """Lazily iterate over the nodes in the tree, starting at self.
+
+        :param include_self: whether to include self in the output
+        :param order: `pre` (parents before children) or `post` (children before parents)
+        """
+
+    def find(
+        self,
+        name: Optional[str] = None,
+        *,
+        attrs: Optional[Mapping[str, str]] = None,
+        meta: Optional[Mapping[str, Any]] = None,
+        predicate: Optional[Callable[["Node"], bool]] = None,
+        max_depth: Optional[int] = None,
+    ) -> Optional["Node"]:
+        """Return the first descendant node satisfying all of the given criteria, or None.
+
+        :param name: the name of the node
+        :param attrs: attributes that the node must have
+        :param meta: meta fields that the node must have
+        :param predicate: a function which must return True for the node
+        :param max_depth: the maximum depth to search, where 1 is the children of self
+        """
+
+    def find_all(
+        self,
+        name: Optional[str] = None,
+        *,
+        attrs: Optional[Mapping[str, str]] = None,
+        meta: Optional[Mapping[str, Any]] = None,
+        predicate: Optional[Callable[["Node"], bool]] = None,
+        max_depth: Optional[int] = None,
+    ) -> List["Node"]:
+        """Return all descendant nodes satisfying all of the given criteria, in depth-first order.
+
+        :param name: the name of the node
+        :param attrs: attributes that the node must have
+        :param meta: meta fields that the node must have
+        :param predicate: a function which must return True for the node
+        :param max_depth: the maximum depth to search, where 1 is the children of self
+        """
+
+    def select(self, selector: str) -> List["Node"]:
+        """Return all descendant nodes matching a CSS-like selector, in depth-first order.
+
+        Supported syntax:
+
+        - `name`: nodes with the given name (`*` matches any name)
+        - `[key]`: nodes with an attribute or meta field `key`
+        - `[key=value]`: nodes with an attribute or meta field `key` equal to `value`,
+          other operators are `!=`, `^=` (starts with), `$=` (ends with) and `*=` (contains)
+        - `a b`: nodes matching `b` that are descendants of a node matching `a`
+        - `a > b`: nodes matching `b` that are direct children of a node matching `a`
+        - `a, b`: nodes matching either `a` or `b`
+
+        :param selector: e.g. `heading[level=2] > text`
+        :raises ValueError: If the selector is invalid.
+        """
+
+    def select_one(self, selector: str) -> Optional["Node"]:
+        """Return the first descendant node matching a CSS-like selector, or None.
+
+        :param selector: e.g. `heading[level=2] > text`
+        :raises ValueError: If the selector is invalid.
+        """
+
+    def text_content(self) -> str:
+        """Return the concatenated text of the node and its descendants,
+        i.e. the `content` of text and code nodes, with line breaks as newlines.
+
+        This is similar to `textContent` in the DOM; for a readable plain text rendering
+        of a document, see `MarkdownIt.render_text`.
+        """
+
     def pretty(
         self,
         *,
@@ -66,6 +254,595 @@ class Node:
         :param indent_current: number of spaces to indent the current level
         """
 
+class Root(Node):
+    """The root of the document (`root`)."""
+
+    def __init__(self, name: str = "root") -> None: ...
+
+class Text(Node):
+    """Plain text (`text`)."""
+
+    def __init__(self, name: str = "text") -> None: ...
+    content: str
+    """`meta["content"]`"""
+
+class TextSpecial(Node):
+    """Text produced from an escape sequence or entity (`text_special`)."""
+
+    def __init__(self, name: str = "text_special") -> None: ...
+    content: str
+    """`meta["content"]`"""
+    markup: str
+    """`meta["markup"]`"""
+    info: str
+    """`meta["info"]`"""
+
+class Blockquote(Node):
+    """A block quote (`blockquote`)."""
+
+    def __init__(self, name: str = "blockquote") -> None: ...
+
+class CodeBlock(Node):
+    """An indented code block (`code_block`)."""
+
+    def __init__(self, name: str = "code_block") -> None: ...
+    content: str
+    """`meta["content"]`"""
+
+class Fence(Node):
+    """A fenced code block (`fence`)."""
+
+    def __init__(self, name: str = "fence") -> None: ...
+    info: str
+    """`meta["info"]`"""
+    marker: str
+    """`meta["marker"]`"""
+    marker_len: int
+    """`meta["marker_len"]`"""
+    content: str
+    """`meta["content"]`"""
+    lang_prefix: str
+    """`meta["lang_prefix"]`"""
+
+class Heading(Node):
+    """An ATX heading (`heading`)."""
+
+    def __init__(self, name: str = "heading") -> None: ...
+    level: int
+    """`meta["level"]`"""
+
+class Hr(Node):
+    """A thematic break (`hr`)."""
+
+    def __init__(self, name: str = "hr") -> None: ...
+    marker: str
+    """`meta["marker"]`"""
+    marker_len: int
+    """`meta["marker_len"]`"""
+
+class LHeading(Node):
+    """A setext heading (`lheading`)."""
+
+    def __init__(self, name: str = "lheading") -> None: ...
+    level: int
+    """`meta["level"]`"""
+    marker: str
+    """`meta["marker"]`"""
+
+class BulletList(Node):
+    """A bullet list (`bullet_list`)."""
+
+    def __init__(self, name: str = "bullet_list") -> None: ...
+    marker: str
+    """`meta["marker"]`"""
+
+class OrderedList(Node):
+    """An ordered list (`ordered_list`)."""
+
+    def __init__(self, name: str = "ordered_list") -> None: ...
+    start: int
+    """`meta["start"]`"""
+    marker: str
+    """`meta["marker"]`"""
+
+class ListItem(Node):
+    """An item of a list (`list_item`)."""
+
+    def __init__(self, name: str = "list_item") -> None: ...
+
+class Paragraph(Node):
+    """A paragraph (`paragraph`)."""
+
+    def __init__(self, name: str = "paragraph") -> None: ...
+
+class Autolink(Node):
+    """An autolink, e.g. `<https://example.com>` (`autolink`)."""
+
+    def __init__(self, name: str = "autolink") -> None: ...
+    url: str
+    """`meta["url"]`"""
+
+class CodeInline(Node):
+    """Inline code (`code_inline`)."""
+
+    def __init__(self, name: str = "code_inline") -> None: ...
+    marker: str
+    """`meta["marker"]`"""
+    marker_len: int
+    """`meta["marker_len"]`"""
+
+class Em(Node):
+    """Emphasis (`em`)."""
+
+    def __init__(self, name: str = "em") -> None: ...
+    marker: str
+    """`meta["marker"]`"""
+
+class Strong(Node):
+    """Strong emphasis (`strong`)."""
+
+    def __init__(self, name: str = "strong") -> None: ...
+    marker: str
+    """`meta["marker"]`"""
+
+class Image(Node):
+    """An image (`image`)."""
+
+    def __init__(self, name: str = "image") -> None: ...
+    url: str
+    """`meta["url"]`"""
+    title: Optional[str]
+    """`meta["title"]` (None if absent)"""
+
+class Link(Node):
+    """A link (`link`)."""
+
+    def __init__(self, name: str = "link") -> None: ...
+    url: str
+    """`meta["url"]`"""
+    title: Optional[str]
+    """`meta["title"]` (None if absent)"""
+
+class Definition(Node):
+    """A link reference definition (`definition`)."""
+
+    def __init__(self, name: str = "definition") -> None: ...
+    label: str
+    """`meta["label"]`"""
+    destination: str
+    """`meta["destination"]`"""
+    title: Optional[str]
+    """`meta["title"]` (None if absent)"""
+
+class Hardbreak(Node):
+    """A hard line break (`hardbreak`)."""
+
+    def __init__(self, name: str = "hardbreak") -> None: ...
+
+class Softbreak(Node):
+    """A soft line break (`softbreak`)."""
+
+    def __init__(self, name: str = "softbreak") -> None: ...
+
+class HtmlInline(Node):
+    """Inline HTML (`html_inline`)."""
+
+    def __init__(self, name: str = "html_inline") -> None: ...
+    content: str
+    """`meta["content"]`"""
+
+class HtmlBlock(Node):
+    """A block of HTML (`html_block`)."""
+
+    def __init__(self, name: str = "html_block") -> None: ...
+    content: str
+    """`meta["content"]`"""
+
+class Linkify(Node):
+    """A link detected by the `linkify` plugin (`linkify`)."""
+
+    def __init__(self, name: str = "linkify") -> None: ...
+    url: str
+    """`meta["url"]`"""
+
+class Strikethrough(Node):
+    """Strikethrough text (`strikethrough`)."""
+
+    def __init__(self, name: str = "strikethrough") -> None: ...
+    marker: str
+    """`meta["marker"]`"""
+
+class Table(Node):
+    """A table (`table`)."""
+
+    def __init__(self, name: str = "table") -> None: ...
+    alignments: List[str]
+    """`meta["alignments"]`"""
+
+class TableBody(Node):
+    """The body of a table (`tbody`)."""
+
+    def __init__(self, name: str = "tbody") -> None: ...
+
+class TableRow(Node):
+    """A row of a table (`trow`)."""
+
+    def __init__(self, name: str = "trow") -> None: ...
+
+class TableCell(Node):
+    """A cell of a table (`tcell`)."""
+
+    def __init__(self, name: str = "tcell") -> None: ...
+
+class TableHead(Node):
+    """The head of a table (`thead`)."""
+
+    def __init__(self, name: str = "thead") -> None: ...
+
+class FrontMatter(Node):
+    """Front matter, at the start of the document (`front_matter`)."""
+
+    def __init__(self, name: str = "front_matter") -> None: ...
+    content: str
+    """`meta["content"]`"""
+
+class TodoCheckbox(Node):
+    """A task list checkbox (`todo_checkbox`)."""
+
+    def __init__(self, name: str = "todo_checkbox") -> None: ...
+    checked: bool
+    """`meta["checked"]`"""
+    disabled: bool
+    """`meta["disabled"]`"""
+
+class FootnoteInline(Node):
+    """An inline footnote, e.g. `^[note]` (`footnote_inline`)."""
+
+    def __init__(self, name: str = "footnote_inline") -> None: ...
+
+class FootnoteRef(Node):
+    """A reference to a footnote (`footnote_ref`)."""
+
+    def __init__(self, name: str = "footnote_ref") -> None: ...
+    def_id: int
+    """`meta["def_id"]`"""
+    ref_id: int
+    """`meta["ref_id"]`"""
+    label: Optional[str]
+    """`meta["label"]` (None if absent)"""
+
+class FootnoteDef(Node):
+    """A footnote definition (`footnote_def`)."""
+
+    def __init__(self, name: str = "footnote_def") -> None: ...
+    def_id: Optional[int]
+    """`meta["def_id"]`"""
+    inline: bool
+    """`meta["inline"]`"""
+    label: Optional[str]
+    """`meta["label"]` (None if absent)"""
+
+class FootnoteContainer(Node):
+    """The container of the footnote definitions, at the end of the document (`footnote_container`)."""
+
+    def __init__(self, name: str = "footnote_container") -> None: ...
+
+class FootnoteRefAnchor(Node):
+    """The back-references of a footnote definition (`footnote_ref_anchor`)."""
+
+    def __init__(self, name: str = "footnote_ref_anchor") -> None: ...
+    ref_ids: List[int]
+    """`meta["ref_ids"]`"""
+
+class HeadingAnchor(Node):
+    """An anchor link for a heading (`heading_anchor`)."""
+
+    def __init__(self, name: str = "heading_anchor") -> None: ...
+    href: str
+    """`meta["href"]`"""
+    id: Optional[str]
+    """`meta["id"]` (None if absent)"""
+
+class DefinitionList(Node):
+    """A definition list (`definition_list`)."""
+
+    def __init__(self, name: str = "definition_list") -> None: ...
+
+class DefinitionTerm(Node):
+    """A term of a definition list (`definition_term`)."""
+
+    def __init__(self, name: str = "definition_term") -> None: ...
+
+class DefinitionDescription(Node):
+    """A description of a definition list (`definition_description`)."""
+
+    def __init__(self, name: str = "definition_description") -> None: ...
+
+class MathInline(Node):
+    """Inline math, e.g. `$x$` (`math_inline`)."""
+
+    def __init__(self, name: str = "math_inline") -> None: ...
+    content: str
+    """`meta["content"]`"""
+    display: bool
+    """`meta["display"]`"""
+
+class MathBlock(Node):
+    """A block of math, e.g. `$$x$$` or an amsmath environment (`math_block`)."""
+
+    def __init__(self, name: str = "math_block") -> None: ...
+    content: str
+    """`meta["content"]`"""
+    label: Optional[str]
+    """`meta["label"]` (None if absent)"""
+    environment: Optional[str]
+    """`meta["environment"]` (None if absent)"""
+
+class Container(Node):
+    """A fenced container, e.g. `::: warning` (`container`)."""
+
+    def __init__(self, name: str = "container") -> None: ...
+    container_name: str
+    """`meta["name"]`"""
+    info: str
+    """`meta["info"]`"""
+    marker_len: int
+    """`meta["marker_len"]`"""
+
+class Alert(Node):
+    """A GitHub alert, e.g. `> [!NOTE]` (`alert`)."""
+
+    def __init__(self, name: str = "alert") -> None: ...
+    kind: str
+    """`meta["kind"]`"""
+
+class Wikilink(Node):
+    """A wiki link, e.g. `[[Page|label]]` (`wikilink`)."""
+
+    def __init__(self, name: str = "wikilink") -> None: ...
+    target: str
+    """`meta["target"]`"""
+    anchor: Optional[str]
+    """`meta["anchor"]` (None if absent)"""
+    label: Optional[str]
+    """`meta["label"]` (None if absent)"""
+    url: str
+    """`meta["url"]`"""
+
+class Emoji(Node):
+    """An emoji shortcode, e.g. `:smile:` (`emoji`)."""
+
+    def __init__(self, name: str = "emoji") -> None: ...
+    markup: str
+    """`meta["markup"]`"""
+    content: str
+    """`meta["content"]`"""
+    url: Optional[str]
+    """`meta["url"]` (None if absent)"""
+
+class Subscript(Node):
+    """Subscript text, e.g. `~x~` (`sub`)."""
+
+    def __init__(self, name: str = "sub") -> None: ...
+    marker: str
+    """`meta["marker"]`"""
+
+class Superscript(Node):
+    """Superscript text, e.g. `^x^` (`sup`)."""
+
+    def __init__(self, name: str = "sup") -> None: ...
+    marker: str
+    """`meta["marker"]`"""
+
+class Mark(Node):
+    """Marked (highlighted) text, e.g. `==x==` (`mark`)."""
+
+    def __init__(self, name: str = "mark") -> None: ...
+    marker: str
+    """`meta["marker"]`"""
+
+class Inserted(Node):
+    """Inserted text, e.g. `++x++` (`ins`)."""
+
+    def __init__(self, name: str = "ins") -> None: ...
+    marker: str
+    """`meta["marker"]`"""
+
+class Abbreviation(Node):
+    """An abbreviation, e.g. `HTML`, with the title of its definition (`abbr`)."""
+
+    def __init__(self, name: str = "abbr") -> None: ...
+    title: str
+    """`meta["title"]`"""
+
+class AbbreviationDefinition(Node):
+    """An abbreviation definition, e.g. `*[HTML]: Hyper Text Markup Language` (`abbr_definition`)."""
+
+    def __init__(self, name: str = "abbr_definition") -> None: ...
+    label: str
+    """`meta["label"]`"""
+    title: str
+    """`meta["title"]`"""
+
+class TableOfContents(Node):
+    """A table of contents, replacing a `[[toc]]` paragraph (`toc`)."""
+
+    def __init__(self, name: str = "toc") -> None: ...
+
+class NodeWalker(Iterator[Node]):
+    """Lazy iterator over the nodes of a tree."""
+
+    def __iter__(self) -> "NodeWalker": ...
+    def __next__(self) -> Node: ...
+    def skip_children(self) -> None:
+        """Do not descend into the children of the node that was last yielded.
+
+        This is only available for pre-order traversal.
+        """
+
+class NodeVisitor:
+    """A node visitor base class that walks the tree and calls a
+    visitor function for every node found.
+
+    This class is meant to be subclassed, with the subclass adding visitor methods,
+    named `visit_` + the node name, e.g. `visit_heading`.
+    If no visitor method exists for a node, `generic_visit` is called instead,
+    which visits all children of the node.
+    """
+
+    def visit(self, node: Node) -> Any:
+        """Visit a node, by calling the `visit_<name>` method for it,
+        or `generic_visit` if no such method exists.
+
+        :returns: the value returned by the visitor method
+        """
+
+    def generic_visit(self, node: Node) -> Any:
+        """Called if no explicit visitor method exists for a node,
+        visits all children of the node.
+        """
+
+class NodeTransformer(NodeVisitor):
+    """A `NodeVisitor` subclass that walks the tree and allows modification of nodes.
+
+    The return value of the visitor methods is used to replace the visited node:
+    if it is `None` the node is removed, if it is a node the node is replaced by it,
+    and if it is a list of nodes the node is replaced by all of them.
+    """
+
+    def visit(self, node: Node) -> Union[None, Node, List[Node]]:
+        """Visit a node, by calling the `visit_<name>` method for it,
+        or `generic_visit` if no such method exists.
+
+        :returns: the value returned by the visitor method
+        """
+
+    def generic_visit(self, node: Node) -> Node:
+        """Called if no explicit visitor method exists for a node,
+        visits all children of the node and replaces them by the results.
+
+        :returns: the node
+        """
+
+_EVENT = Union[Tuple[Literal["enter", "exit"], Node], Tuple[Literal["text"], str]]
+
+class EventStream(Iterator[_EVENT]):
+    """Iterator of `("enter", node)`, `("exit", node)` and `("text", content)` events,
+    generated lazily from the syntax tree.
+
+    Nodes are created without children, and text nodes only produce a `text` event.
+    """
+
+    def __iter__(self) -> "EventStream": ...
+    def __next__(self) -> _EVENT: ...
+
+class TreeEdit:
+    """A single edit operation, to transform one tree into another."""
+
+    @property
+    def op(self) -> Literal["insert", "delete", "update", "move"]:
+        """The type of operation."""
+
+    @property
+    def path_a(self) -> Optional[List[int]]:
+        """The path of child indices to the node in the old tree (None for `insert`)."""
+
+    @property
+    def path_b(self) -> Optional[List[int]]:
+        """The path of child indices to the node in the new tree (None for `delete`)."""
+
+    @property
+    def node_a(self) -> Optional[Node]:
+        """The node in the old tree (None for `insert`)."""
+
+    @property
+    def node_b(self) -> Optional[Node]:
+        """The node in the new tree (None for `delete`)."""
+
+    @property
+    def srcmap_a(self) -> Optional[Tuple[int, int]]:
+        """Byte offset mapping of the node in the old tree."""
+
+    @property
+    def srcmap_b(self) -> Optional[Tuple[int, int]]:
+        """Byte offset mapping of the node in the new tree."""
+
+    @property
+    def changes(self) -> Mapping[str, Tuple[Any, Any]]:
+        """For `update`, a mapping of changed fields (`name`, `attrs.<key>` or `meta.<key>`)
+        to their `(old, new)` values, where a missing value is None.
+        """
+
+def diff_trees(a: Node, b: Node) -> List[TreeEdit]:
+    """Compute the edit operations required to transform tree `a` into tree `b`.
+
+    The children of matched nodes are matched in order if they are similar,
+    i.e. identical, or with the same name and at least half the words of their text content
+    (`content` meta values) in common.
+    Similar children that are out of order are also matched, and produce a `move`.
+    Then, between the matched children, any remaining children with the same name are matched.
+
+    Matched nodes with a differing name, attrs or meta produce an `update`,
+    and unmatched nodes produce a `delete` or `insert`,
+    except that a deleted and an inserted node that are identical
+    (e.g. moved to another parent) produce a single `move`.
+
+    :param a: The old tree.
+    :param b: The new tree.
+    """
+
+class _MetaSchema(TypedDict):
+    type: str
+    """The Python type of the value, e.g. `str`, `list[int]` or `int | None`."""
+    optional: bool
+    """Whether the key may be absent."""
+
+class _NodeSchema(TypedDict):
+    plugins: List[_PLUGIN_NAME]
+    """The plugins that produce the node (empty for the core parser)."""
+    _rust_path: str
+    """The rust module path of the node type."""
+    meta: Dict[str, _MetaSchema]
+    """The meta keys of the node."""
+
+def node_schema() -> Dict[str, _NodeSchema]:
+    """Return the schema of every node type that can be created from a syntax tree,
+    as a mapping of node names to the plugins that produce them,
+    their `_rust_path` and their meta keys.
+
+    Node types that have no dedicated conversion are not included.
+    """
+
+class Prose:
+    """Prose text extracted from a Markdown source,
+    with a mapping from ranges of the text back to the source.
+    """
+
+    @property
+    def text(self) -> str:
+        """The extracted text."""
+
+    @property
+    def source(self) -> str:
+        """The Markdown source."""
+
+    def segments(self, *, bytes: bool = False) -> List[Tuple[int, int, int, int]]:
+        """Return the segments of the text that originate from the source,
+        as `(start, end, src_start, src_end)`.
+
+        Text offsets are character offsets, and source offsets are character
+        offsets, or byte offsets (as used by `Node.srcmap`) if `bytes` is True.
+        """
+
+    def source_span(self, start: int, end: int, *, bytes: bool = False) -> Tuple[int, int]:
+        """Map a range of the text (as character offsets) to the corresponding range of the source.
+
+        :param start: The start of the range in the text.
+        :param end: The end of the range in the text.
+        :param bytes: Return byte offsets, rather than character offsets.
+        :raises ValueError: If the range is invalid.
+        """
+
 _PLUGIN_NAME = Literal[
     "blockquote",
     "code",
@@ -98,30 +875,58 @@ _PLUGIN_NAME = Literal[
     "heading_anchors",
     "autolink_ext",
     "deflist",
+    "dollarmath",
+    "amsmath",
+    "container",
+    "alerts",
+    "attrs",
+    "wikilinks",
+    "emoji",
+    "sub",
+    "sup",
+    "mark",
+    "ins",
+    "abbr",
+    "toc",
 ]
 
 class MarkdownIt:
-    """Markdown parser class."""
+    """Markdown parser class.
+
+    Parsers can be pickled, and are re-created from their configuration preset
+    and the plugins enabled on them.
+    """
 
     def __init__(
-        self, config: Literal["commonmark", "gfm", "zero"] = "commonmark"
+        self,
+        config: Literal["commonmark", "gfm", "zero"] = "commonmark",
+        *,
+        cache_size: int = 0,
     ) -> None:
         """Initialize a MarkdownIt instance.
 
         :param config: Configuration preset name.
+        :param cache_size: The maximum number of results of `render` and `tree` to cache,
+            keyed by the source, with the least recently used discarded first.
+            The cache is cleared when the parser configuration changes.
         """
 
+    def cache_info(self) -> Mapping[str, int]:
+        """Return statistics of the parse cache: `hits`, `misses`, `maxsize` and `currsize`."""
+
+    def cache_clear(self) -> None:
+        """Clear the parse cache."""
+
     @staticmethod
     def list_plugins() -> List[_PLUGIN_NAME]:
         """List available plugins."""
 
-    def enable(
-        self,
-        name: _PLUGIN_NAME,
-    ) -> "MarkdownIt":
+    def enable(self, name: _PLUGIN_NAME, **options: Any) -> "MarkdownIt":
         """Enable a plugin rule.
 
         :param name: Plugin name.
+        :param options: Options for the plugin (see the README for the options of each plugin).
+        :raises ValueError: If the plugin is unknown, or does not accept an option.
         """
 
     def enable_many(
@@ -141,8 +946,47 @@ class MarkdownIt:
         :returns: HTML.
         """
 
+    def render_text(self, src: str) -> str:
+        """Render Markdown to readable plain text.
+
+        Markup is removed, except for list bullets, blockquote and table cell separators,
+        and images are replaced by their alt text.
+
+        :param src: Markdown source.
+        :returns: Plain text.
+        """
+
+    def prose(self, src: str, *, code: bool = False) -> Prose:
+        """Extract the prose text from Markdown,
+        with a mapping from ranges of the text back to the source.
+
+        Markup (such as list bullets), HTML and, unless `code` is True, code is excluded.
+
+        :param src: Markdown source.
+        :param code: Whether to include inline code and code blocks.
+        """
+
+    def render_diff(self, old_src: str, new_src: str, *, xhtml: bool = True) -> str:
+        """Render the differences between two versions of a Markdown source to HTML,
+        with inserted and deleted content marked up by `<ins>` and `<del>` tags.
+
+        Changes are marked up at the level of blocks, inline elements and words.
+
+        :param old_src: The old Markdown source.
+        :param new_src: The new Markdown source.
+        :param xhtml: If true, self-closing tags will include a slash, e.g. `<br />`.
+        :returns: HTML.
+        """
+
     def tree(self, src: str) -> Node:
         """Create a syntax tree from the Markdown source.
 
         :param src: Markdown source.
         """
+
+    def events(self, src: str) -> EventStream:
+        """Create a stream of `("enter", node)`, `("exit", node)` and `("text", content)`
+        events from the Markdown source, without building the full syntax tree.
+
+        :param src: Markdown source.
+        """
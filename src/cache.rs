@@ -0,0 +1,128 @@
+//! A least-recently-used cache of parse results, keyed by a hash of the source.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// The type of output that is cached
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+pub enum CacheKind {
+    Html,
+    XHtml,
+    Tree,
+}
+
+#[derive(Clone)]
+pub enum CacheValue {
+    Html(String),
+    /// A syntax tree, serialized with `Node.to_bytes`
+    Tree(Vec<u8>),
+}
+
+struct Entry {
+    kind: CacheKind,
+    /// The source, to guard against hash collisions
+    src: String,
+    value: CacheValue,
+    last_used: u64,
+}
+
+pub struct ParseCache {
+    max_size: usize,
+    entries: HashMap<u64, Entry>,
+    /// Keys of the entries, ordered by when they were last used
+    order: BTreeMap<u64, u64>,
+    tick: u64,
+    hits: usize,
+    misses: usize,
+}
+
+impl std::fmt::Debug for ParseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParseCache")
+            .field("max_size", &self.max_size)
+            .finish()
+    }
+}
+
+impl ParseCache {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn key(kind: CacheKind, src: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        kind.hash(&mut hasher);
+        src.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn get(&mut self, kind: CacheKind, src: &str) -> Option<CacheValue> {
+        let key = Self::key(kind, src);
+        match self.entries.get_mut(&key) {
+            Some(entry) if entry.kind == kind && entry.src == src => {
+                self.hits += 1;
+                self.tick += 1;
+                self.order.remove(&entry.last_used);
+                self.order.insert(self.tick, key);
+                entry.last_used = self.tick;
+                Some(entry.value.clone())
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, kind: CacheKind, src: &str, value: CacheValue) {
+        if self.max_size == 0 {
+            return;
+        }
+        let key = Self::key(kind, src);
+        if let Some(entry) = self.entries.remove(&key) {
+            self.order.remove(&entry.last_used);
+        }
+        while self.entries.len() >= self.max_size {
+            match self.order.pop_first() {
+                Some((_, oldest)) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        self.tick += 1;
+        self.order.insert(self.tick, key);
+        self.entries.insert(
+            key,
+            Entry {
+                kind,
+                src: src.to_string(),
+                value,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Return the cache statistics, as `(hits, misses, max_size, current_size)`
+    pub fn info(&self) -> (usize, usize, usize, usize) {
+        (self.hits, self.misses, self.max_size, self.entries.len())
+    }
+}
@@ -0,0 +1,347 @@
+//! Typed subclasses of `Node`, one per known node type,
+//! exposing the node's `meta` entries as typed properties.
+use pyo3::prelude::*;
+use pyo3::PyClassInitializer;
+
+use crate::nodes::Node;
+
+/// A `meta` entry exposed as a property of a typed node class
+pub struct MetaField {
+    pub key: &'static str,
+    /// The Python type of the value
+    pub py_type: &'static str,
+    /// Whether the key may be absent from `meta`
+    pub optional: bool,
+}
+
+/// Read a meta value, treating a missing key as `None` (if allowed by the type)
+fn get_meta<T: for<'a> FromPyObject<'a>>(py: Python, node: &Node, key: &str) -> PyResult<T> {
+    match node.meta.get(key) {
+        Some(value) => value.extract(py),
+        None => py.None().extract(py).map_err(|_| {
+            pyo3::exceptions::PyAttributeError::new_err(format!(
+                "Node '{}' has no meta key: {}",
+                node.name, key
+            ))
+        }),
+    }
+}
+
+/// Write a meta value, removing optional keys that are set to `None`
+fn set_meta(py: Python, node: &mut Node, key: &str, value: PyObject, optional: bool) {
+    if optional && value.is_none(py) {
+        node.meta.remove(key);
+    } else {
+        node.meta.insert(key.to_string(), value);
+    }
+}
+
+macro_rules! optional {
+    () => {
+        false
+    };
+    (optional) => {
+        true
+    };
+}
+
+macro_rules! meta_key {
+    ($field:ident) => {
+        stringify!($field)
+    };
+    ($field:ident $key:literal) => {
+        $key
+    };
+}
+
+/// Properties are named after their meta key, unless given as `property("key")`
+macro_rules! node_classes {
+    ($(
+        $(#[doc = $doc:literal])*
+        $class:ident($name:literal) {
+            $($field:ident $(($key:literal))? / $setter:ident: $ty:ty = $py_type:literal $(, $optional:ident)?;)*
+        }
+    )*) => {
+        $(
+            $(#[doc = $doc])*
+            #[pyclass(extends = Node, module = "markdown_it_pyrs")]
+            pub struct $class;
+
+            impl $class {
+                pub const FIELDS: &'static [MetaField] = &[$(MetaField {
+                    key: meta_key!($field $($key)?),
+                    py_type: $py_type,
+                    optional: optional!($($optional)?),
+                },)*];
+            }
+
+            #[pymethods]
+            impl $class {
+                #[new]
+                #[pyo3(signature = (name=$name))]
+                fn new(name: &str) -> (Self, Node) {
+                    ($class, Node::new(name))
+                }
+
+                $(
+                    #[getter]
+                    fn $field(slf: PyRef<Self>) -> PyResult<$ty> {
+                        get_meta(slf.py(), slf.as_ref(), meta_key!($field $($key)?))
+                    }
+
+                    #[setter]
+                    fn $setter(mut slf: PyRefMut<Self>, value: $ty) {
+                        let py = slf.py();
+                        let optional = optional!($($optional)?);
+                        let key = meta_key!($field $($key)?);
+                        set_meta(py, slf.as_mut(), key, value.into_py(py), optional);
+                    }
+                )*
+            }
+        )*
+
+        /// Move a node into Python, as an instance of the typed class for its name (if any)
+        pub fn into_py_node(py: Python, node: Node) -> PyResult<Py<Node>> {
+            let object: PyObject = match node.name.as_str() {
+                $($name => Py::new(py, PyClassInitializer::from(node).add_subclass($class))?.into_py(py),)*
+                _ => return Py::new(py, node),
+            };
+            object.extract(py)
+        }
+
+        /// The meta fields of the typed class for a node name
+        pub fn meta_fields(name: &str) -> &'static [MetaField] {
+            match name {
+                $($name => $class::FIELDS,)*
+                _ => &[],
+            }
+        }
+
+        pub fn add_classes(m: &PyModule) -> PyResult<()> {
+            $(m.add_class::<$class>()?;)*
+            Ok(())
+        }
+    };
+}
+
+node_classes! {
+    /// The root of the document.
+    Root("root") {}
+    /// Plain text.
+    Text("text") {
+        content / set_content: String = "str";
+    }
+    /// Text produced from an escape sequence or entity.
+    TextSpecial("text_special") {
+        content / set_content: String = "str";
+        markup / set_markup: String = "str";
+        info / set_info: String = "str";
+    }
+    /// A block quote.
+    Blockquote("blockquote") {}
+    /// An indented code block.
+    CodeBlock("code_block") {
+        content / set_content: String = "str";
+    }
+    /// A fenced code block.
+    Fence("fence") {
+        info / set_info: String = "str";
+        marker / set_marker: String = "str";
+        marker_len / set_marker_len: usize = "int";
+        content / set_content: String = "str";
+        lang_prefix / set_lang_prefix: String = "str";
+    }
+    /// An ATX heading.
+    Heading("heading") {
+        level / set_level: u8 = "int";
+    }
+    /// A thematic break.
+    Hr("hr") {
+        marker / set_marker: String = "str";
+        marker_len / set_marker_len: usize = "int";
+    }
+    /// A setext heading.
+    LHeading("lheading") {
+        level / set_level: u8 = "int";
+        marker / set_marker: String = "str";
+    }
+    /// A bullet list.
+    BulletList("bullet_list") {
+        marker / set_marker: String = "str";
+    }
+    /// An ordered list.
+    OrderedList("ordered_list") {
+        start / set_start: u32 = "int";
+        marker / set_marker: String = "str";
+    }
+    /// An item of a list.
+    ListItem("list_item") {}
+    /// A paragraph.
+    Paragraph("paragraph") {}
+    /// An autolink, e.g. `<https://example.com>`.
+    Autolink("autolink") {
+        url / set_url: String = "str";
+    }
+    /// Inline code.
+    CodeInline("code_inline") {
+        marker / set_marker: String = "str";
+        marker_len / set_marker_len: usize = "int";
+    }
+    /// Emphasis.
+    Em("em") {
+        marker / set_marker: String = "str";
+    }
+    /// Strong emphasis.
+    Strong("strong") {
+        marker / set_marker: String = "str";
+    }
+    /// An image.
+    Image("image") {
+        url / set_url: String = "str";
+        title / set_title: Option<String> = "str", optional;
+    }
+    /// A link.
+    Link("link") {
+        url / set_url: String = "str";
+        title / set_title: Option<String> = "str", optional;
+    }
+    /// A link reference definition.
+    Definition("definition") {
+        label / set_label: String = "str";
+        destination / set_destination: String = "str";
+        title / set_title: Option<String> = "str", optional;
+    }
+    /// A hard line break.
+    Hardbreak("hardbreak") {}
+    /// A soft line break.
+    Softbreak("softbreak") {}
+    /// Inline HTML.
+    HtmlInline("html_inline") {
+        content / set_content: String = "str";
+    }
+    /// A block of HTML.
+    HtmlBlock("html_block") {
+        content / set_content: String = "str";
+    }
+    /// A link detected by the `linkify` plugin.
+    Linkify("linkify") {
+        url / set_url: String = "str";
+    }
+    /// Strikethrough text.
+    Strikethrough("strikethrough") {
+        marker / set_marker: String = "str";
+    }
+    /// A table.
+    Table("table") {
+        alignments / set_alignments: Vec<String> = "list[str]";
+    }
+    /// The body of a table.
+    TableBody("tbody") {}
+    /// A row of a table.
+    TableRow("trow") {}
+    /// A cell of a table.
+    TableCell("tcell") {}
+    /// The head of a table.
+    TableHead("thead") {}
+    /// Front matter, at the start of the document.
+    FrontMatter("front_matter") {
+        content / set_content: String = "str";
+    }
+    /// A task list checkbox.
+    TodoCheckbox("todo_checkbox") {
+        checked / set_checked: bool = "bool";
+        disabled / set_disabled: bool = "bool";
+    }
+    /// An inline footnote, e.g. `^[note]`.
+    FootnoteInline("footnote_inline") {}
+    /// A reference to a footnote.
+    FootnoteRef("footnote_ref") {
+        def_id / set_def_id: usize = "int";
+        ref_id / set_ref_id: usize = "int";
+        label / set_label: Option<String> = "str", optional;
+    }
+    /// A footnote definition.
+    FootnoteDef("footnote_def") {
+        def_id / set_def_id: Option<usize> = "int | None";
+        inline / set_inline: bool = "bool";
+        label / set_label: Option<String> = "str", optional;
+    }
+    /// The container of the footnote definitions, at the end of the document.
+    FootnoteContainer("footnote_container") {}
+    /// The back-references of a footnote definition.
+    FootnoteRefAnchor("footnote_ref_anchor") {
+        ref_ids / set_ref_ids: Vec<usize> = "list[int]";
+    }
+    /// An anchor link for a heading.
+    HeadingAnchor("heading_anchor") {
+        href / set_href: String = "str";
+        id / set_id: Option<String> = "str", optional;
+    }
+    /// A definition list.
+    DefinitionList("definition_list") {}
+    /// A term of a definition list.
+    DefinitionTerm("definition_term") {}
+    /// A description of a definition list.
+    DefinitionDescription("definition_description") {}
+    /// Inline math, e.g. `$x$`.
+    MathInline("math_inline") {
+        content / set_content: String = "str";
+        display / set_display: bool = "bool";
+    }
+    /// A block of math, e.g. `$$x$$` or an amsmath environment.
+    MathBlock("math_block") {
+        content / set_content: String = "str";
+        label / set_label: Option<String> = "str", optional;
+        environment / set_environment: Option<String> = "str", optional;
+    }
+    /// A fenced container, e.g. `::: warning`.
+    Container("container") {
+        container_name("name") / set_container_name: String = "str";
+        info / set_info: String = "str";
+        marker_len / set_marker_len: usize = "int";
+    }
+    /// A GitHub alert, e.g. `> [!NOTE]`.
+    Alert("alert") {
+        kind / set_kind: String = "str";
+    }
+    /// A wiki link, e.g. `[[Page|label]]`.
+    Wikilink("wikilink") {
+        target / set_target: String = "str";
+        anchor / set_anchor: Option<String> = "str", optional;
+        label / set_label: Option<String> = "str", optional;
+        url / set_url: String = "str";
+    }
+    /// An emoji shortcode, e.g. `:smile:`.
+    Emoji("emoji") {
+        markup / set_markup: String = "str";
+        content / set_content: String = "str";
+        url / set_url: Option<String> = "str", optional;
+    }
+    /// Subscript text, e.g. `~x~`.
+    Subscript("sub") {
+        marker / set_marker: String = "str";
+    }
+    /// Superscript text, e.g. `^x^`.
+    Superscript("sup") {
+        marker / set_marker: String = "str";
+    }
+    /// Marked (highlighted) text, e.g. `==x==`.
+    Mark("mark") {
+        marker / set_marker: String = "str";
+    }
+    /// Inserted text, e.g. `++x++`.
+    Inserted("ins") {
+        marker / set_marker: String = "str";
+    }
+    /// An abbreviation, e.g. `HTML`, with the title of its definition.
+    Abbreviation("abbr") {
+        title / set_title: String = "str";
+    }
+    /// An abbreviation definition, e.g. `*[HTML]: Hyper Text Markup Language`.
+    AbbreviationDefinition("abbr_definition") {
+        label / set_label: String = "str";
+        title / set_title: String = "str";
+    }
+    /// A table of contents, replacing a `[[toc]]` paragraph.
+    TableOfContents("toc") {}
+}
@@ -0,0 +1,427 @@
+//! Compute the differences between two Node trees.
+use itertools::Itertools;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::AsPyPointer;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::nodes::Node;
+
+/// Return the longest common subsequence of two sequences,
+/// as a list of index pairs `(index_a, index_b)`.
+pub fn lcs<T>(a: &[T], b: &[T], eq: impl Fn(&T, &T) -> bool) -> Vec<(usize, usize)> {
+    // trim the common prefix and suffix, which is the most common case
+    let mut start = 0;
+    while start < a.len() && start < b.len() && eq(&a[start], &b[start]) {
+        start += 1;
+    }
+    let mut end_a = a.len();
+    let mut end_b = b.len();
+    while end_a > start && end_b > start && eq(&a[end_a - 1], &b[end_b - 1]) {
+        end_a -= 1;
+        end_b -= 1;
+    }
+
+    let mut pairs: Vec<(usize, usize)> = (0..start).map(|i| (i, i)).collect();
+    let (n, m) = (end_a - start, end_b - start);
+    if n > 0 && m > 0 {
+        // lengths[i][j] is the LCS length of a[start + i..end_a] and b[start + j..end_b]
+        let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i][j] = if eq(&a[start + i], &b[start + j]) {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if eq(&a[start + i], &b[start + j]) {
+                pairs.push((start + i, start + j));
+                i += 1;
+                j += 1;
+            } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+    pairs.extend((0..a.len() - end_a).map(|k| (end_a + k, end_b + k)));
+    pairs
+}
+
+/// A single edit operation, to transform one tree into another.
+#[pyclass]
+pub struct TreeEdit {
+    #[pyo3(get)]
+    /// The type of operation: `insert`, `delete`, `update` or `move`
+    pub op: &'static str,
+
+    #[pyo3(get)]
+    /// The path of child indices to the node in the old tree (None for `insert`)
+    pub path_a: Option<Vec<usize>>,
+
+    #[pyo3(get)]
+    /// The path of child indices to the node in the new tree (None for `delete`)
+    pub path_b: Option<Vec<usize>>,
+
+    #[pyo3(get)]
+    /// The node in the old tree (None for `insert`)
+    pub node_a: Option<Py<Node>>,
+
+    #[pyo3(get)]
+    /// The node in the new tree (None for `delete`)
+    pub node_b: Option<Py<Node>>,
+
+    #[pyo3(get)]
+    /// For `update`, a mapping of changed fields (`name`, `attrs.<key>` or `meta.<key>`)
+    /// to their `(old, new)` values, where a missing value is None.
+    pub changes: Py<PyDict>,
+}
+
+#[pymethods]
+impl TreeEdit {
+    #[getter]
+    /// Byte offset mapping of the node in the old tree
+    fn srcmap_a(&self, py: Python) -> Option<(usize, usize)> {
+        self.node_a.as_ref().and_then(|n| n.borrow(py).srcmap)
+    }
+
+    #[getter]
+    /// Byte offset mapping of the node in the new tree
+    fn srcmap_b(&self, py: Python) -> Option<(usize, usize)> {
+        self.node_b.as_ref().and_then(|n| n.borrow(py).srcmap)
+    }
+
+    fn __repr__(&self, py: Python) -> String {
+        let name = match (&self.node_a, &self.node_b) {
+            (_, Some(node)) | (Some(node), None) => node.borrow(py).name.clone(),
+            (None, None) => String::new(),
+        };
+        let path = |path: &Option<Vec<usize>>| match path {
+            Some(path) => format!("/{}", path.iter().join("/")),
+            None => "-".to_string(),
+        };
+        format!(
+            "TreeEdit({}, {}, {} -> {})",
+            self.op,
+            name,
+            path(&self.path_a),
+            path(&self.path_b)
+        )
+    }
+}
+
+/// Compute a hash of a node's content (excluding source mapping), and that of its descendants.
+/// Hashes are stored in `hashes`, keyed by the node's pointer.
+fn hash_tree(py: Python, node: &Py<Node>, hashes: &mut HashMap<usize, u64>) -> PyResult<u64> {
+    let node_ref = node.borrow(py);
+    let mut hasher = DefaultHasher::new();
+    node_ref.name.hash(&mut hasher);
+    for (key, value) in node_ref.attrs.iter().sorted() {
+        key.hash(&mut hasher);
+        value.hash(&mut hasher);
+    }
+    for (key, value) in node_ref.meta.iter().sorted_by(|a, b| a.0.cmp(b.0)) {
+        key.hash(&mut hasher);
+        value.as_ref(py).repr()?.to_str()?.hash(&mut hasher);
+    }
+    for child in node_ref.children.iter() {
+        stacker::maybe_grow(64 * 1024, 1024 * 1024, || hash_tree(py, child, hashes))?
+            .hash(&mut hasher);
+    }
+    let hash = hasher.finish();
+    hashes.insert(node.as_ptr() as usize, hash);
+    Ok(hash)
+}
+
+/// The words of the text content (i.e. `content` meta values) of a node and its descendants
+fn tree_words(py: Python, node: &Py<Node>) -> PyResult<HashSet<String>> {
+    let mut words = HashSet::new();
+    let mut nodes = node.borrow(py)._walk(py);
+    nodes.push(node.clone_ref(py));
+    for node in nodes {
+        if let Some(content) = node.borrow(py).meta.get("content") {
+            if let Ok(content) = content.extract::<&str>(py) {
+                words.extend(content.split_whitespace().map(|w| w.to_string()));
+            }
+        }
+    }
+    Ok(words)
+}
+
+struct TreeDiffer<'py> {
+    py: Python<'py>,
+    hashes: HashMap<usize, u64>,
+    words: HashMap<usize, HashSet<String>>,
+    edits: Vec<TreeEdit>,
+}
+
+impl<'py> TreeDiffer<'py> {
+    fn hash(&self, node: &Py<Node>) -> u64 {
+        self.hashes[&(node.as_ptr() as usize)]
+    }
+
+    /// Whether two nodes are identical, or have the same name and similar text content
+    fn similar(&mut self, a: &Py<Node>, b: &Py<Node>) -> PyResult<bool> {
+        if self.hash(a) == self.hash(b) {
+            return Ok(true);
+        }
+        if a.borrow(self.py).name != b.borrow(self.py).name {
+            return Ok(false);
+        }
+        for node in [a, b] {
+            let key = node.as_ptr() as usize;
+            if !self.words.contains_key(&key) {
+                let words = tree_words(self.py, node)?;
+                self.words.insert(key, words);
+            }
+        }
+        let words_a = &self.words[&(a.as_ptr() as usize)];
+        let words_b = &self.words[&(b.as_ptr() as usize)];
+        let union = words_a.union(words_b).count();
+        if union == 0 {
+            return Ok(true);
+        }
+        let intersection = words_a.intersection(words_b).count();
+        Ok(intersection * 2 >= union)
+    }
+
+    fn edit(
+        &self,
+        op: &'static str,
+        a: Option<(&Py<Node>, &[usize])>,
+        b: Option<(&Py<Node>, &[usize])>,
+    ) -> TreeEdit {
+        TreeEdit {
+            op,
+            path_a: a.map(|(_, path)| path.to_vec()),
+            path_b: b.map(|(_, path)| path.to_vec()),
+            node_a: a.map(|(node, _)| node.clone_ref(self.py)),
+            node_b: b.map(|(node, _)| node.clone_ref(self.py)),
+            changes: PyDict::new(self.py).into(),
+        }
+    }
+
+    /// Compare the data of two matched nodes, and record an update if it differs
+    fn compare_data(
+        &mut self,
+        a: &Py<Node>,
+        path_a: &[usize],
+        b: &Py<Node>,
+        path_b: &[usize],
+    ) -> PyResult<()> {
+        let py = self.py;
+        let changes = PyDict::new(py);
+        {
+            let (a_ref, b_ref) = (a.borrow(py), b.borrow(py));
+            if a_ref.name != b_ref.name {
+                changes.set_item("name", (&a_ref.name, &b_ref.name))?;
+            }
+            for key in a_ref
+                .attrs
+                .keys()
+                .chain(b_ref.attrs.keys())
+                .unique()
+                .sorted()
+            {
+                let (old, new) = (a_ref.attrs.get(key), b_ref.attrs.get(key));
+                if old != new {
+                    changes.set_item(format!("attrs.{}", key), (old, new))?;
+                }
+            }
+            for key in a_ref.meta.keys().chain(b_ref.meta.keys()).unique().sorted() {
+                let (old, new) = (a_ref.meta.get(key), b_ref.meta.get(key));
+                let equal = match (old, new) {
+                    (Some(old), Some(new)) => old.as_ref(py).eq(new)?,
+                    _ => false,
+                };
+                if !equal {
+                    changes.set_item(format!("meta.{}", key), (old, new))?;
+                }
+            }
+        }
+        if !changes.is_empty() {
+            let mut edit = self.edit("update", Some((a, path_a)), Some((b, path_b)));
+            edit.changes = changes.into();
+            self.edits.push(edit);
+        }
+        Ok(())
+    }
+
+    /// Diff two matched nodes, and recursively their children
+    fn diff_nodes(
+        &mut self,
+        a: &Py<Node>,
+        path_a: &mut Vec<usize>,
+        b: &Py<Node>,
+        path_b: &mut Vec<usize>,
+    ) -> PyResult<()> {
+        if self.hash(a) == self.hash(b) {
+            return Ok(());
+        }
+        self.compare_data(a, path_a, b, path_b)?;
+
+        let children_a = a.borrow(self.py).children.clone();
+        let children_b = b.borrow(self.py).children.clone();
+
+        // align children that are identical or similar
+        let mut similar = vec![vec![false; children_b.len()]; children_a.len()];
+        for (i, child_a) in children_a.iter().enumerate() {
+            for (j, child_b) in children_b.iter().enumerate() {
+                similar[i][j] = self.similar(child_a, child_b)?;
+            }
+        }
+        let indices_a: Vec<usize> = (0..children_a.len()).collect();
+        let indices_b: Vec<usize> = (0..children_b.len()).collect();
+        let mut pairs = lcs(&indices_a, &indices_b, |i, j| similar[*i][*j]);
+
+        // remaining children that are similar have been moved within the parent
+        let mut paired_a = vec![false; children_a.len()];
+        let mut paired_b = vec![false; children_b.len()];
+        for (i, j) in pairs.iter() {
+            paired_a[*i] = true;
+            paired_b[*j] = true;
+        }
+        let mut moves = Vec::new();
+        for (i, similar_i) in similar.iter().enumerate() {
+            if paired_a[i] {
+                continue;
+            }
+            if let Some(j) = (0..children_b.len()).find(|j| !paired_b[*j] && similar_i[*j]) {
+                paired_a[i] = true;
+                paired_b[j] = true;
+                moves.push((i, j));
+            }
+        }
+
+        // within the gaps between aligned children, pair up children with the same name
+        let names_a: Vec<String> = children_a
+            .iter()
+            .map(|n| n.borrow(self.py).name.clone())
+            .collect();
+        let names_b: Vec<String> = children_b
+            .iter()
+            .map(|n| n.borrow(self.py).name.clone())
+            .collect();
+        let mut anchors = pairs.clone();
+        anchors.push((children_a.len(), children_b.len()));
+        let (mut start_a, mut start_b) = (0, 0);
+        for (end_a, end_b) in anchors {
+            let gap_a: Vec<usize> = (start_a..end_a).filter(|i| !paired_a[*i]).collect();
+            let gap_b: Vec<usize> = (start_b..end_b).filter(|j| !paired_b[*j]).collect();
+            for (i, j) in lcs(&gap_a, &gap_b, |i, j| names_a[*i] == names_b[*j]) {
+                pairs.push((gap_a[i], gap_b[j]));
+            }
+            (start_a, start_b) = (end_a + 1, end_b + 1);
+        }
+
+        for (_, j) in pairs.iter() {
+            paired_b[*j] = true;
+        }
+        let pair_of_a: HashMap<usize, (usize, bool)> = pairs
+            .iter()
+            .map(|(i, j)| (*i, (*j, false)))
+            .chain(moves.iter().map(|(i, j)| (*i, (*j, true))))
+            .collect();
+
+        for (i, child_a) in children_a.iter().enumerate() {
+            path_a.push(i);
+            match pair_of_a.get(&i) {
+                Some((j, moved)) => {
+                    path_b.push(*j);
+                    if *moved {
+                        self.edits.push(self.edit(
+                            "move",
+                            Some((child_a, path_a)),
+                            Some((&children_b[*j], path_b)),
+                        ));
+                    }
+                    stacker::maybe_grow(64 * 1024, 1024 * 1024, || {
+                        self.diff_nodes(child_a, path_a, &children_b[*j], path_b)
+                    })?;
+                    path_b.pop();
+                }
+                None => {
+                    self.edits
+                        .push(self.edit("delete", Some((child_a, path_a)), None));
+                }
+            }
+            path_a.pop();
+        }
+        for (j, child_b) in children_b.iter().enumerate() {
+            if !paired_b[j] {
+                path_b.push(j);
+                self.edits
+                    .push(self.edit("insert", None, Some((child_b, path_b))));
+                path_b.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Combine deletions and insertions of identical nodes (in different parents) into moves
+    fn detect_moves(&mut self) {
+        let edits = std::mem::take(&mut self.edits);
+        let mut inserts: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (index, edit) in edits.iter().enumerate() {
+            if edit.op == "insert" {
+                let hash = self.hash(edit.node_b.as_ref().unwrap());
+                inserts.entry(hash).or_default().push(index);
+            }
+        }
+        let mut moved: HashMap<usize, usize> = HashMap::new();
+        for (index, edit) in edits.iter().enumerate() {
+            if edit.op == "delete" {
+                let hash = self.hash(edit.node_a.as_ref().unwrap());
+                if let Some(candidates) = inserts.get_mut(&hash) {
+                    if !candidates.is_empty() {
+                        moved.insert(index, candidates.remove(0));
+                    }
+                }
+            }
+        }
+        let targets: HashSet<usize> = moved.values().copied().collect();
+        let mut edits: Vec<Option<TreeEdit>> = edits.into_iter().map(Some).collect();
+        for index in 0..edits.len() {
+            if targets.contains(&index) {
+                continue;
+            }
+            let mut edit = edits[index].take().unwrap();
+            if let Some(target) = moved.get(&index) {
+                let insert = edits[*target].as_ref().unwrap();
+                edit.op = "move";
+                edit.path_b = insert.path_b.clone();
+                edit.node_b = insert.node_b.as_ref().map(|n| n.clone_ref(self.py));
+            }
+            self.edits.push(edit);
+        }
+    }
+}
+
+/// Compute the edit operations required to transform tree `a` into tree `b`.
+///
+/// Children are aligned by first matching similar nodes (see `TreeDiffer::similar`),
+/// where those out of order are moved, then nodes with the same name between them,
+/// and matched nodes are compared recursively.
+/// Deleted and inserted nodes that are identical are reported as moves.
+#[pyfunction]
+pub fn diff_trees(py: Python, a: Py<Node>, b: Py<Node>) -> PyResult<Vec<TreeEdit>> {
+    let mut hashes = HashMap::new();
+    hash_tree(py, &a, &mut hashes)?;
+    hash_tree(py, &b, &mut hashes)?;
+    let mut differ = TreeDiffer {
+        py,
+        hashes,
+        words: HashMap::new(),
+        edits: Vec::new(),
+    };
+    differ.diff_nodes(&a, &mut Vec::new(), &b, &mut Vec::new())?;
+    differ.detect_moves();
+    Ok(differ.edits)
+}
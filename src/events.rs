@@ -0,0 +1,93 @@
+//! A streaming (SAX-style) interface to the parsed syntax tree.
+use pyo3::prelude::*;
+
+use crate::classes::into_py_node;
+use crate::nodes::{create_node, Node};
+
+struct Frame {
+    /// The index of the next child to visit
+    next_child: usize,
+    /// The Python representation of the node, returned again on exit
+    py_node: Py<Node>,
+}
+
+/// Iterator of `("enter", node)`, `("exit", node)` and `("text", content)` events,
+/// generated lazily from the syntax tree.
+///
+/// Nodes are created without children, and text nodes only produce a `text` event.
+// the syntax tree is not `Send`, so the iterator must stay on the thread it was created in
+#[pyclass(unsendable)]
+pub struct EventStream {
+    ast: markdown_it::Node,
+    stack: Vec<Frame>,
+    started: bool,
+}
+
+impl EventStream {
+    pub fn new(ast: markdown_it::Node) -> Self {
+        Self {
+            ast,
+            stack: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Return the text content, if the node should be emitted as a `text` event
+    fn text_content(node: &markdown_it::Node) -> Option<&str> {
+        if let Some(value) = node.cast::<markdown_it::parser::inline::Text>() {
+            Some(&value.content)
+        } else if let Some(value) = node.cast::<markdown_it::parser::inline::TextSpecial>() {
+            Some(&value.content)
+        } else {
+            None
+        }
+    }
+}
+
+#[pymethods]
+impl EventStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<Option<(&'static str, PyObject)>> {
+        if !self.started {
+            self.started = true;
+            let py_node = into_py_node(py, create_node(py, &self.ast))?;
+            self.stack.push(Frame {
+                next_child: 0,
+                py_node: py_node.clone_ref(py),
+            });
+            return Ok(Some(("enter", py_node.into_py(py))));
+        }
+        if self.stack.is_empty() {
+            return Ok(None);
+        }
+
+        // resolve the current node from the path of child indices
+        let mut node = &self.ast;
+        for frame in self.stack[..self.stack.len() - 1].iter() {
+            node = &node.children[frame.next_child - 1];
+        }
+
+        let frame = self.stack.last_mut().unwrap();
+        match node.children.get(frame.next_child) {
+            Some(child) => {
+                frame.next_child += 1;
+                if let Some(content) = EventStream::text_content(child) {
+                    return Ok(Some(("text", content.into_py(py))));
+                }
+                let py_node = into_py_node(py, create_node(py, child))?;
+                self.stack.push(Frame {
+                    next_child: 0,
+                    py_node: py_node.clone_ref(py),
+                });
+                Ok(Some(("enter", py_node.into_py(py))))
+            }
+            None => {
+                let frame = self.stack.pop().unwrap();
+                Ok(Some(("exit", frame.py_node.into_py(py))))
+            }
+        }
+    }
+}
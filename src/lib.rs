@@ -1,16 +1,46 @@
+// pyo3 v0.19 `#[pymethods]` expands to impl blocks that newer versions of rustc lint against
+#![allow(non_local_definitions)]
+
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::Mutex;
+
+use cache::{CacheKind, CacheValue, ParseCache};
 
+mod cache;
+mod classes;
+mod diff;
+mod events;
 mod nodes;
+mod plugins;
+mod redline;
+mod registry;
+mod select;
+mod serialize;
+mod text;
+mod visitor;
+mod walk;
 
 /// Main parser class
-#[pyclass]
+#[pyclass(module = "markdown_it_pyrs")]
 #[derive(Debug)]
 pub struct MarkdownIt {
     parser: markdown_it::MarkdownIt,
+    /// The configuration preset the parser was created from
+    config: String,
+    /// The plugins enabled after creation, in order, with their options
+    plugins: Vec<(String, Option<Py<PyDict>>)>,
+    lang_prefix_unset: bool,
+    /// Cache of rendered HTML and syntax trees, which is cleared when the configuration changes
+    cache: Mutex<ParseCache>,
 }
 
 impl MarkdownIt {
-    fn _enable(&mut self, name: &str) -> Result<(), PyErr> {
+    fn _enable(&mut self, name: &str, options: Option<&PyDict>) -> Result<(), PyErr> {
+        let mut plugin_options = plugins::Options::new(name, options);
+        if !plugins::configurable(name) {
+            plugin_options.finish()?;
+        }
         match name {
             "blockquote" => {
                 markdown_it::plugins::cmark::block::blockquote::add(&mut self.parser);
@@ -105,6 +135,45 @@ impl MarkdownIt {
             "deflist" => {
                 markdown_it_deflist::add(&mut self.parser);
             }
+            "dollarmath" => {
+                plugins::math::add_dollarmath(&mut self.parser, &mut plugin_options)?;
+            }
+            "amsmath" => {
+                plugins::math::add_amsmath(&mut self.parser, &mut plugin_options)?;
+            }
+            "container" => {
+                plugins::container::add(&mut self.parser);
+            }
+            "alerts" => {
+                plugins::alerts::add(&mut self.parser);
+            }
+            "attrs" => {
+                plugins::attrs::add(&mut self.parser, &mut plugin_options)?;
+            }
+            "wikilinks" => {
+                plugins::wikilinks::add(&mut self.parser, &mut plugin_options)?;
+            }
+            "emoji" => {
+                plugins::emoji::add(&mut self.parser, &mut plugin_options)?;
+            }
+            "sub" => {
+                plugins::pairs::add_sub(&mut self.parser);
+            }
+            "sup" => {
+                plugins::pairs::add_sup(&mut self.parser);
+            }
+            "mark" => {
+                plugins::pairs::add_mark(&mut self.parser);
+            }
+            "ins" => {
+                plugins::pairs::add_ins(&mut self.parser);
+            }
+            "abbr" => {
+                plugins::abbr::add(&mut self.parser);
+            }
+            "toc" => {
+                plugins::toc::add(&mut self.parser, &mut plugin_options)?;
+            }
             _ => {
                 return {
                     Err(pyo3::exceptions::PyValueError::new_err(format!(
@@ -114,35 +183,132 @@ impl MarkdownIt {
                 }
             }
         }
+        let options = options.map(|o| o.copy()).transpose()?.map(Into::into);
+        self.plugins.push((name.to_string(), options));
+        self.cache.get_mut().unwrap().clear();
         Ok(())
     }
+
+    // Note, the cache is only locked during lookup and insertion,
+    // since other threads may run whilst parsing or copying (if the GIL is released)
+    fn cache_get(&self, kind: CacheKind, src: &str) -> Option<CacheValue> {
+        let mut cache = self.cache.lock().unwrap();
+        match cache.max_size() {
+            0 => None,
+            _ => cache.get(kind, src),
+        }
+    }
+
+    fn cache_insert(&self, kind: CacheKind, src: &str, value: impl FnOnce() -> CacheValue) {
+        let mut cache = self.cache.lock().unwrap();
+        if cache.max_size() > 0 {
+            cache.insert(kind, src, value());
+        }
+    }
+
+    /// Parse the markdown string, raising any error from Python code called by plugins
+    fn parse(&self, src: &str) -> PyResult<markdown_it::Node> {
+        let mut ast = self.parser.parse(src);
+        plugins::take_error(&mut ast)?;
+        Ok(ast)
+    }
+
+    /// Create a syntax tree from the markdown string (without caching)
+    fn _tree(&self, py: Python, src: &str) -> PyResult<Py<nodes::Node>> {
+        let ast = self.parse(src)?;
+
+        fn walk_recursive(
+            py: Python,
+            node: &markdown_it::Node,
+            py_node: &Py<nodes::Node>,
+        ) -> PyResult<()> {
+            for n in node.children.iter() {
+                let mut py_node_child = nodes::create_node(py, n);
+                py_node_child.parent = Some(py_node.clone_ref(py));
+                let py_node_child = classes::into_py_node(py, py_node_child)?;
+
+                stacker::maybe_grow(64 * 1024, 1024 * 1024, || {
+                    walk_recursive(py, n, &py_node_child)
+                })?;
+
+                py_node.borrow_mut(py).children.push(py_node_child);
+            }
+            Ok(())
+        }
+
+        let py_node = classes::into_py_node(py, nodes::create_node(py, &ast))?;
+        walk_recursive(py, &ast, &py_node)?;
+
+        Ok(py_node)
+    }
 }
 
 #[pymethods]
 impl MarkdownIt {
     #[new]
-    #[pyo3(signature = (config="commonmark"))]
-    fn new(config: &str) -> PyResult<Self> {
+    #[pyo3(signature = (config="commonmark", *, cache_size=0))]
+    fn new(config: &str, cache_size: usize) -> PyResult<Self> {
+        let mut parser = markdown_it::MarkdownIt::new();
         match config {
             "commonmark" => {
-                let mut parser = markdown_it::MarkdownIt::new();
                 markdown_it::plugins::cmark::add(&mut parser);
                 markdown_it::plugins::html::add(&mut parser);
-                Ok(Self { parser })
             }
             "gfm" => {
-                let mut parser = markdown_it::MarkdownIt::new();
                 markdown_it_gfm::add(&mut parser);
-                Ok(Self { parser })
-            }
-            "zero" => Ok(Self {
-                parser: markdown_it::MarkdownIt::new(),
-            }),
-            _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
-                "Unknown config: {}",
-                config
-            ))),
+                plugins::alerts::add(&mut parser);
+                plugins::emoji::add_default(&mut parser);
+            }
+            "zero" => {}
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Unknown config: {}",
+                    config
+                )))
+            }
+        }
+        Ok(Self {
+            parser,
+            config: config.to_string(),
+            plugins: Vec::new(),
+            lang_prefix_unset: false,
+            cache: Mutex::new(ParseCache::new(cache_size)),
+        })
+    }
+
+    /// Pickle the parser as its configuration preset, and the plugins/options applied to it.
+    fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, (String,), PyObject)> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let state = (
+            this.plugins
+                .iter()
+                .map(|(name, options)| (name.clone(), options.as_ref().map(|o| o.clone_ref(py))))
+                .collect::<Vec<_>>(),
+            this.lang_prefix_unset,
+            this.cache.lock().unwrap().max_size(),
+        );
+        Ok((
+            slf.get_type().into(),
+            (this.config.clone(),),
+            state.into_py(py),
+        ))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn __setstate__(
+        &mut self,
+        state: (Vec<(String, Option<&PyDict>)>, bool, usize),
+    ) -> PyResult<()> {
+        let (plugins, lang_prefix_unset, cache_size) = state;
+        self.cache = Mutex::new(ParseCache::new(cache_size));
+        for (name, options) in plugins {
+            self._enable(&name, options)?;
+        }
+        if lang_prefix_unset {
+            self._unset_lang_prefix();
         }
+        Ok(())
     }
 
     /// Return a debug representation of the rust struct
@@ -153,6 +319,25 @@ impl MarkdownIt {
     // keep this private for now, whilst we work out how to expose it properly
     fn _unset_lang_prefix(&mut self) {
         markdown_it::plugins::cmark::block::fence::set_lang_prefix(&mut self.parser, "");
+        self.lang_prefix_unset = true;
+        self.cache.get_mut().unwrap().clear();
+    }
+
+    /// Return statistics of the parse cache, as a dict of
+    /// `hits`, `misses`, `maxsize` and `currsize`.
+    fn cache_info<'py>(&self, py: Python<'py>) -> PyResult<&'py pyo3::types::PyDict> {
+        let (hits, misses, max_size, size) = self.cache.lock().unwrap().info();
+        let info = pyo3::types::PyDict::new(py);
+        info.set_item("hits", hits)?;
+        info.set_item("misses", misses)?;
+        info.set_item("maxsize", max_size)?;
+        info.set_item("currsize", size)?;
+        Ok(info)
+    }
+
+    /// Clear the parse cache.
+    fn cache_clear(&self) {
+        self.cache.lock().unwrap().clear();
     }
 
     #[staticmethod]
@@ -189,22 +374,40 @@ impl MarkdownIt {
             "heading_anchors",
             "autolink_ext",
             "deflist",
+            "dollarmath",
+            "amsmath",
+            "container",
+            "alerts",
+            "attrs",
+            "wikilinks",
+            "emoji",
+            "sub",
+            "sup",
+            "mark",
+            "ins",
+            "abbr",
+            "toc",
         ]
         .iter()
         .map(|s| s.to_string())
         .collect()
     }
 
-    /// Enable a plugin
-    fn enable(slf: Py<Self>, py: Python, name: &str) -> PyResult<Py<Self>> {
-        slf.borrow_mut(py)._enable(name)?;
+    /// Enable a plugin, with options passed as keyword arguments
+    #[pyo3(signature = (name, **options))]
+    fn enable<'p>(
+        slf: &'p PyCell<Self>,
+        name: &str,
+        options: Option<&PyDict>,
+    ) -> PyResult<&'p PyCell<Self>> {
+        slf.borrow_mut()._enable(name, options)?;
         Ok(slf)
     }
 
     /// Enable multiple plugins
-    fn enable_many(slf: Py<Self>, py: Python, names: Vec<&str>) -> PyResult<Py<Self>> {
+    fn enable_many<'p>(slf: &'p PyCell<Self>, names: Vec<&str>) -> PyResult<&'p PyCell<Self>> {
         for name in names {
-            slf.borrow_mut(py)._enable(name)?;
+            slf.borrow_mut()._enable(name, None)?;
         }
         Ok(slf)
     }
@@ -212,34 +415,75 @@ impl MarkdownIt {
     /// Render markdown string into HTML.
     /// If `xhtml` is true, then self-closing tags will include a slash, e.g. `<br />`.
     #[pyo3(signature = (src, *, xhtml=true))]
-    fn render(&self, src: &str, xhtml: bool) -> String {
-        let ast = self.parser.parse(src);
-        match xhtml {
+    fn render(&self, src: &str, xhtml: bool) -> PyResult<String> {
+        let kind = match xhtml {
+            true => CacheKind::XHtml,
+            false => CacheKind::Html,
+        };
+        if let Some(CacheValue::Html(html)) = self.cache_get(kind, src) {
+            return Ok(html);
+        }
+        let ast = self.parse(src)?;
+        let html = match xhtml {
             true => ast.xrender(),
             false => ast.render(),
-        }
+        };
+        self.cache_insert(kind, src, || CacheValue::Html(html.clone()));
+        Ok(html)
     }
 
-    /// Create a syntax tree from the markdown string.
-    fn tree(&self, py: Python, src: &str) -> nodes::Node {
-        let ast = self.parser.parse(src);
+    /// Render markdown string to readable plain text.
+    ///
+    /// Markup is removed, except for list bullets, blockquote and table cell separators,
+    /// and images are replaced by their alt text.
+    fn render_text(&self, src: &str) -> PyResult<String> {
+        Ok(text::TextRenderer::new(true, true)
+            .render(&self.parse(src)?)
+            .0)
+    }
 
-        fn walk_recursive(py: Python, node: &markdown_it::Node, py_node: &mut nodes::Node) {
-            for n in node.children.iter() {
-                let mut py_node_child = nodes::create_node(py, n);
+    /// Extract the prose text from a markdown string,
+    /// with a mapping from ranges of the text back to the source.
+    ///
+    /// Markup (such as list bullets), HTML and, unless `code` is True, code is excluded.
+    #[pyo3(signature = (src, *, code=false))]
+    fn prose(&self, src: &str, code: bool) -> PyResult<text::Prose> {
+        Ok(text::Prose::new(src, &self.parse(src)?, code))
+    }
 
-                stacker::maybe_grow(64 * 1024, 1024 * 1024, || {
-                    walk_recursive(py, n, &mut py_node_child);
-                });
+    /// Render the differences between two versions of a markdown string to HTML,
+    /// with inserted and deleted content marked up by `<ins>` and `<del>` tags.
+    ///
+    /// Changes are marked up at the level of blocks, inline elements and words.
+    #[pyo3(signature = (old_src, new_src, *, xhtml=true))]
+    fn render_diff(&self, old_src: &str, new_src: &str, xhtml: bool) -> PyResult<String> {
+        let ast = redline::merge_trees(self.parse(old_src)?, self.parse(new_src)?);
+        Ok(match xhtml {
+            true => ast.xrender(),
+            false => ast.render(),
+        })
+    }
 
-                py_node.children.push(Py::new(py, py_node_child).unwrap());
+    /// Create a syntax tree from the markdown string.
+    fn tree(&self, py: Python, src: &str) -> PyResult<Py<nodes::Node>> {
+        // the tree is cached in its serialized form, since the returned tree may be modified
+        if let Some(CacheValue::Tree(data)) = self.cache_get(CacheKind::Tree, src) {
+            return serialize::from_bytes(py, &data);
+        }
+        let tree = self._tree(py, src)?;
+        if self.cache.lock().unwrap().max_size() > 0 {
+            // a tree with meta values that cannot be serialized is not cached
+            if let Ok(data) = serialize::to_bytes(py, &tree) {
+                self.cache_insert(CacheKind::Tree, src, || CacheValue::Tree(data));
             }
         }
+        Ok(tree)
+    }
 
-        let mut py_node = nodes::create_node(py, &ast);
-        walk_recursive(py, &ast, &mut py_node);
-
-        py_node
+    /// Create a stream of `("enter", node)`, `("exit", node)` and `("text", content)`
+    /// events from the markdown string, without building the full syntax tree.
+    fn events(&self, src: &str) -> PyResult<events::EventStream> {
+        Ok(events::EventStream::new(self.parse(src)?))
     }
 }
 
@@ -251,6 +495,15 @@ fn markdown_it_pyrs(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add("__version__", env!("CARGO_PKG_VERSION"))?;
     m.add_class::<MarkdownIt>()?;
     m.add_class::<nodes::Node>()?;
+    classes::add_classes(m)?;
+    m.add_class::<walk::NodeWalker>()?;
+    m.add_class::<events::EventStream>()?;
+    m.add_class::<visitor::NodeVisitor>()?;
+    m.add_class::<visitor::NodeTransformer>()?;
+    m.add_class::<diff::TreeEdit>()?;
+    m.add_class::<text::Prose>()?;
+    m.add_function(wrap_pyfunction!(diff::diff_trees, m)?)?;
+    m.add_function(wrap_pyfunction!(registry::node_schema, m)?)?;
     // let plugins_module = PyModule::new(py, "plugins")?;
     // plugins_module.add_function(wrap_pyfunction!(plugins::add_heading_anchors, plugins_module)?)?;
     // m.add_submodule(plugins_module)?;
@@ -1,8 +1,16 @@
 use itertools::Itertools;
+use pyo3::basic::CompareOp;
 use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyLong, PyString};
+use pyo3::{PyTraverseError, PyVisit};
 use std::collections::HashMap;
 
-#[pyclass]
+use crate::classes::into_py_node;
+use crate::registry::{convert_unknown, registry};
+use crate::select::SelectorList;
+use crate::walk::{NodeFilter, NodeWalker};
+
+#[pyclass(module = "markdown_it_pyrs", subclass)]
 /// Single node in the Markdown AST tree.
 pub struct Node {
     #[pyo3(get, set)]
@@ -13,11 +21,13 @@ pub struct Node {
     /// The type of the node
     pub name: String,
 
-    #[pyo3(get, set)]
     /// Array of child nodes.
     // See https://github.com/PyO3/pyo3/discussions/3223#discussioncomment-6144333
     pub children: Vec<Py<Node>>,
 
+    /// The node that this node is a child of.
+    pub parent: Option<Py<Node>>,
+
     #[pyo3(get, set)]
     /// Byte offset mapping of the (start, end) of the source syntax.
     pub srcmap: Option<(usize, usize)>,
@@ -32,7 +42,7 @@ pub struct Node {
 }
 
 impl Node {
-    fn _walk(&self, py: Python) -> Vec<Py<Node>> {
+    pub(crate) fn _walk(&self, py: Python) -> Vec<Py<Node>> {
         let mut nodes: Vec<Py<Node>> = Vec::new();
         for child in self.children.iter() {
             nodes.push(child.clone_ref(py));
@@ -43,10 +53,224 @@ impl Node {
         nodes
     }
 
+    /// Append the text content of the node and its descendants
+    fn collect_text(&self, py: Python, text: &mut String) {
+        match self.name.as_str() {
+            "softbreak" | "hardbreak" => text.push('\n'),
+            // markup rather than text
+            "html_inline" | "html_block" | "front_matter" => {}
+            _ if self.children.is_empty() => {
+                if let Some(Ok(content)) = self.meta.get("content").map(|c| c.extract::<&str>(py)) {
+                    text.push_str(content);
+                }
+            }
+            _ => {
+                for child in self.children.iter() {
+                    child.borrow(py).collect_text(py, text);
+                }
+            }
+        }
+    }
+
     /// add a key/value pair of node type specific data
-    fn add_data(&mut self, key: &str, value: Py<PyAny>) {
+    pub(crate) fn add_data(&mut self, key: &str, value: Py<PyAny>) {
         self.meta.insert(key.to_string(), value);
     }
+
+    /// Structurally compare two nodes and their descendants
+    pub fn equals(&self, py: Python, other: &Node, srcmap: bool) -> PyResult<bool> {
+        if self.name != other.name
+            || self.attrs != other.attrs
+            || (srcmap && self.srcmap != other.srcmap)
+            || self.meta.len() != other.meta.len()
+            || self.children.len() != other.children.len()
+        {
+            return Ok(false);
+        }
+        for (key, value) in self.meta.iter() {
+            match other.meta.get(key) {
+                Some(other_value) => {
+                    if !value.as_ref(py).eq(other_value)? {
+                        return Ok(false);
+                    }
+                }
+                None => return Ok(false),
+            }
+        }
+        for (child, other_child) in self.children.iter().zip(other.children.iter()) {
+            if child.is(other_child) {
+                continue;
+            }
+            let equal = stacker::maybe_grow(64 * 1024, 1024 * 1024, || {
+                child.borrow(py).equals(py, &other_child.borrow(py), srcmap)
+            })?;
+            if !equal {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Create a copy of the node, without its children or parent
+    fn copy_data(&self, py: Python) -> Node {
+        Node {
+            _rust_path: self._rust_path.clone(),
+            name: self.name.clone(),
+            children: Vec::new(),
+            parent: None,
+            srcmap: self.srcmap,
+            attrs: self.attrs.clone(),
+            meta: self
+                .meta
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone_ref(py)))
+                .collect(),
+        }
+    }
+
+    /// Recursively copy a node and its descendants,
+    /// meta values are also copied (using `copy.deepcopy` for non-primitive types).
+    pub fn deep_copy(py: Python, node: &Py<Node>, memo: Option<&PyAny>) -> PyResult<Py<Node>> {
+        let deepcopy = py.import("copy")?.getattr("deepcopy")?;
+
+        fn copy_recursive(
+            py: Python,
+            node: &Py<Node>,
+            deepcopy: &PyAny,
+            memo: Option<&PyAny>,
+        ) -> PyResult<Py<Node>> {
+            let node = node.borrow(py);
+            let mut copy = node.copy_data(py);
+            for value in copy.meta.values_mut() {
+                let value_ref = value.as_ref(py);
+                if !(value_ref.is_none()
+                    || value_ref.is_instance_of::<PyString>()
+                    || value_ref.is_instance_of::<PyBool>()
+                    || value_ref.is_instance_of::<PyLong>()
+                    || value_ref.is_instance_of::<PyFloat>())
+                {
+                    *value = deepcopy.call1((value_ref, memo))?.into_py(py);
+                }
+            }
+            let copy = into_py_node(py, copy)?;
+            let mut children = Vec::with_capacity(node.children.len());
+            for child in node.children.iter() {
+                let child_copy = stacker::maybe_grow(64 * 1024, 1024 * 1024, || {
+                    copy_recursive(py, child, deepcopy, memo)
+                })?;
+                child_copy.borrow_mut(py).parent = Some(copy.clone_ref(py));
+                children.push(child_copy);
+            }
+            copy.borrow_mut(py).children = children;
+            Ok(copy)
+        }
+
+        copy_recursive(py, node, deepcopy, memo)
+    }
+
+    /// Return the index of `child` within the children of `parent`
+    fn index_in(py: Python, parent: &Py<Node>, child: &Py<Node>) -> Option<usize> {
+        parent.borrow(py).children.iter().position(|c| c.is(child))
+    }
+
+    /// Return the parent of `node` and the index of `node` within its children
+    fn position(py: Python, node: &Py<Node>) -> PyResult<(Py<Node>, usize)> {
+        let parent = match &node.borrow(py).parent {
+            Some(parent) => parent.clone_ref(py),
+            None => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "Node has no parent",
+                ))
+            }
+        };
+        match Node::index_in(py, &parent, node) {
+            Some(index) => Ok((parent, index)),
+            None => Err(pyo3::exceptions::PyValueError::new_err(
+                "Node is not a child of its parent",
+            )),
+        }
+    }
+
+    /// Remove `node` from the children of its current parent (if any)
+    pub fn detach(py: Python, node: &Py<Node>) {
+        let parent = node.borrow_mut(py).parent.take();
+        if let Some(parent) = parent {
+            if let Some(index) = Node::index_in(py, &parent, node) {
+                parent.borrow_mut(py).children.remove(index);
+            }
+        }
+    }
+
+    /// Check that none of `nodes` are `parent` or one of its ancestors
+    fn check_insertable(py: Python, parent: &Py<Node>, nodes: &[Py<Node>]) -> PyResult<()> {
+        let mut ancestor = Some(parent.clone_ref(py));
+        while let Some(current) = ancestor {
+            if nodes.iter().any(|n| n.is(&current)) {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "Cannot insert a node into its own subtree",
+                ));
+            }
+            ancestor = current.borrow(py).parent.as_ref().map(|p| p.clone_ref(py));
+        }
+        Ok(())
+    }
+
+    /// Replace all children of `node`, detaching the previous children.
+    pub fn replace_children(py: Python, node: &Py<Node>, children: Vec<Py<Node>>) -> PyResult<()> {
+        Node::check_insertable(py, node, &children)?;
+        let old_children = std::mem::take(&mut node.borrow_mut(py).children);
+        for child in old_children {
+            child.borrow_mut(py).parent = None;
+        }
+        Node::attach(py, node, 0, children)
+    }
+
+    /// Insert `nodes` into the children of `parent` at `index`,
+    /// detaching them from any previous parent.
+    pub fn attach(
+        py: Python,
+        parent: &Py<Node>,
+        index: usize,
+        nodes: Vec<Py<Node>>,
+    ) -> PyResult<()> {
+        Node::check_insertable(py, parent, &nodes)?;
+        let mut index = index;
+        for node in nodes {
+            // detaching may shift the insertion point, if the node is already a prior sibling
+            if let Some(current) = &node.borrow(py).parent {
+                if current.is(parent) {
+                    if let Some(old_index) = Node::index_in(py, parent, &node) {
+                        if old_index < index {
+                            index -= 1;
+                        }
+                    }
+                }
+            }
+            Node::detach(py, &node);
+            node.borrow_mut(py).parent = Some(parent.clone_ref(py));
+            let mut parent_ref = parent.borrow_mut(py);
+            let index_clamped = index.min(parent_ref.children.len());
+            parent_ref.children.insert(index_clamped, node);
+            index = index_clamped + 1;
+        }
+        Ok(())
+    }
+}
+
+/// A single node or a sequence of nodes
+#[derive(FromPyObject)]
+pub enum NodeOrNodes {
+    Single(Py<Node>),
+    Multiple(Vec<Py<Node>>),
+}
+
+impl NodeOrNodes {
+    fn into_vec(self) -> Vec<Py<Node>> {
+        match self {
+            NodeOrNodes::Single(node) => vec![node],
+            NodeOrNodes::Multiple(nodes) => nodes,
+        }
+    }
 }
 
 #[pymethods]
@@ -57,6 +281,7 @@ impl Node {
             _rust_path: None,
             name: name.to_string(),
             children: Vec::new(),
+            parent: None,
             srcmap: None,
             attrs: HashMap::new(),
             meta: HashMap::new(),
@@ -69,20 +294,296 @@ impl Node {
         self.__repr__()
     }
 
+    fn __richcmp__(&self, py: Python, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+        let other = match other.extract::<PyRef<Node>>() {
+            Ok(other) => other,
+            Err(_) => return Ok(py.NotImplemented()),
+        };
+        match op {
+            CompareOp::Eq => Ok(self.equals(py, &other, true)?.into_py(py)),
+            CompareOp::Ne => Ok((!self.equals(py, &other, true)?).into_py(py)),
+            _ => Ok(py.NotImplemented()),
+        }
+    }
+
+    /// Structurally compare this node (and its descendants) with another node.
+    ///
+    /// The name, attrs, meta and children of nodes are compared,
+    /// and also the srcmap, if `srcmap` is True.
+    /// Note, `node1 == node2` is equivalent to `node1.equals(node2, srcmap=True)`.
+    #[pyo3(name = "equals", signature = (other, *, srcmap=true))]
+    fn py_equals(&self, py: Python, other: PyRef<Node>, srcmap: bool) -> PyResult<bool> {
+        self.equals(py, &other, srcmap)
+    }
+
+    /// Return a deep copy of the node (without a parent), the same as `__deepcopy__`.
+    ///
+    /// Children are not shared with the copy, since a node can only have a single parent.
+    fn __copy__(slf: &PyCell<Self>) -> PyResult<Py<Node>> {
+        Node::deep_copy(slf.py(), &slf.into(), None)
+    }
+
+    /// Return a deep copy of the node (without a parent).
+    fn __deepcopy__(slf: &PyCell<Self>, memo: &PyDict) -> PyResult<Py<Node>> {
+        Node::deep_copy(slf.py(), &slf.into(), Some(memo))
+    }
+
+    /// Serialize the node and its descendants to a compact binary format.
+    ///
+    /// Meta values must be None, bool, int, float, str, bytes, or lists, tuples and dicts of these.
+    fn to_bytes(slf: &PyCell<Self>) -> PyResult<&PyBytes> {
+        let data = crate::serialize::to_bytes(slf.py(), &slf.into())?;
+        Ok(PyBytes::new(slf.py(), &data))
+    }
+
+    /// Deserialize a node and its descendants, from the output of `to_bytes`.
+    #[staticmethod]
+    fn from_bytes(py: Python, data: &[u8]) -> PyResult<Py<Node>> {
+        crate::serialize::from_bytes(py, data)
+    }
+
+    fn __getnewargs__(&self) -> (String,) {
+        (self.name.clone(),)
+    }
+
+    /// Return the state of the node (and its descendants) for pickling.
+    fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let state = PyDict::new(py);
+        state.set_item("_rust_path", &self._rust_path)?;
+        state.set_item("srcmap", self.srcmap)?;
+        state.set_item("attrs", &self.attrs)?;
+        state.set_item("meta", &self.meta)?;
+        state.set_item("children", &self.children)?;
+        Ok(state)
+    }
+
+    fn __setstate__(slf: &PyCell<Self>, state: &PyDict) -> PyResult<()> {
+        let get_item = |key: &str| {
+            state.get_item(key).ok_or_else(|| {
+                pyo3::exceptions::PyValueError::new_err(format!("Missing node state: {}", key))
+            })
+        };
+        {
+            let mut node = slf.borrow_mut();
+            node._rust_path = get_item("_rust_path")?.extract()?;
+            node.srcmap = get_item("srcmap")?.extract()?;
+            node.attrs = get_item("attrs")?.extract()?;
+            node.meta = get_item("meta")?.extract()?;
+        }
+        let children: Vec<Py<Node>> = get_item("children")?.extract()?;
+        Node::replace_children(slf.py(), &slf.into(), children)
+    }
+
+    fn __traverse__(&self, visit: PyVisit<'_>) -> Result<(), PyTraverseError> {
+        for child in self.children.iter() {
+            visit.call(child)?;
+        }
+        if let Some(parent) = &self.parent {
+            visit.call(parent)?;
+        }
+        for value in self.meta.values() {
+            visit.call(value)?;
+        }
+        Ok(())
+    }
+
+    fn __clear__(&mut self) {
+        self.children.clear();
+        self.parent = None;
+        self.meta.clear();
+    }
+
+    #[getter]
+    /// Array of child nodes.
+    fn get_children(&self, py: Python) -> Vec<Py<Node>> {
+        self.children.iter().map(|c| c.clone_ref(py)).collect()
+    }
+
+    #[setter]
+    fn set_children(slf: &PyCell<Self>, children: Vec<Py<Node>>) -> PyResult<()> {
+        Node::replace_children(slf.py(), &slf.into(), children)
+    }
+
+    #[getter]
+    /// The parent of this node, or None if it is the root of a tree.
+    fn get_parent(&self, py: Python) -> Option<Py<Node>> {
+        self.parent.as_ref().map(|p| p.clone_ref(py))
+    }
+
+    /// Append a node to the end of the children of this node.
+    ///
+    /// If the node already has a parent, it is first removed from it.
+    fn append(slf: &PyCell<Self>, node: Py<Node>) -> PyResult<()> {
+        let py = slf.py();
+        let index = slf.borrow().children.len();
+        Node::attach(py, &slf.into(), index, vec![node])
+    }
+
+    /// Insert a node into the children of this node, before `index`.
+    ///
+    /// If the node already has a parent, it is first removed from it.
+    fn insert(slf: &PyCell<Self>, index: isize, node: Py<Node>) -> PyResult<()> {
+        let py = slf.py();
+        let length = slf.borrow().children.len() as isize;
+        // mimic the semantics of `list.insert`
+        let index = if index < 0 {
+            (length + index).max(0)
+        } else {
+            index.min(length)
+        };
+        Node::attach(py, &slf.into(), index as usize, vec![node])
+    }
+
+    /// Remove this node from its parent.
+    fn remove(slf: &PyCell<Self>) -> PyResult<()> {
+        let py = slf.py();
+        let slf: Py<Node> = slf.into();
+        Node::position(py, &slf)?;
+        Node::detach(py, &slf);
+        Ok(())
+    }
+
+    /// Replace this node, within its parent, by one or more nodes.
+    fn replace_with(slf: &PyCell<Self>, nodes: NodeOrNodes) -> PyResult<()> {
+        let py = slf.py();
+        let slf: Py<Node> = slf.into();
+        let (parent, index) = Node::position(py, &slf)?;
+        let nodes = nodes.into_vec();
+        Node::check_insertable(py, &parent, &nodes)?;
+        Node::detach(py, &slf);
+        Node::attach(py, &parent, index, nodes)
+    }
+
+    /// Wrap this node in a new node, which takes its place within the parent.
+    ///
+    /// :param name: The type of the new node
+    /// :returns: The new (wrapping) node
+    fn wrap(slf: &PyCell<Self>, name: &str) -> PyResult<Py<Node>> {
+        let py = slf.py();
+        let mut wrapper = Node::new(name);
+        wrapper.srcmap = slf.borrow().srcmap;
+        let wrapper = into_py_node(py, wrapper)?;
+        let slf: Py<Node> = slf.into();
+        if let Ok((parent, index)) = Node::position(py, &slf) {
+            Node::attach(py, &parent, index, vec![wrapper.clone_ref(py)])?;
+        }
+        Node::attach(py, &wrapper, 0, vec![slf])?;
+        Ok(wrapper)
+    }
+
+    /// Remove this node from its parent, moving its children into its place.
+    fn unwrap(slf: &PyCell<Self>) -> PyResult<()> {
+        let py = slf.py();
+        let slf: Py<Node> = slf.into();
+        let (parent, index) = Node::position(py, &slf)?;
+        Node::detach(py, &slf);
+        let children = std::mem::take(&mut slf.borrow_mut(py).children);
+        for child in children.iter() {
+            child.borrow_mut(py).parent = None;
+        }
+        Node::attach(py, &parent, index, children)
+    }
+
     /// Recursively yield all descendant nodes in the tree starting at self.
     ///
     /// The order mimics the order of the underlying linear token
     /// stream (i.e. depth first).
     #[pyo3(signature = (*, include_self=true))]
-    fn walk(slf: Py<Self>, py: Python, include_self: bool) -> Vec<Py<Node>> {
+    fn walk(slf: &PyCell<Self>, include_self: bool) -> Vec<Py<Node>> {
         let mut nodes: Vec<Py<Node>> = Vec::new();
         if include_self {
-            nodes.push(slf.clone_ref(py));
+            nodes.push(slf.into());
         }
-        nodes.extend(slf.borrow(py)._walk(py));
+        nodes.extend(slf.borrow()._walk(slf.py()));
         nodes
     }
 
+    /// Lazily iterate over the nodes in the tree, starting at self.
+    ///
+    /// :param include_self: whether to include self in the output
+    /// :param order: `pre` (parents before children) or `post` (children before parents)
+    #[pyo3(signature = (*, include_self=true, order="pre"))]
+    fn iter_walk(slf: &PyCell<Self>, include_self: bool, order: &str) -> PyResult<NodeWalker> {
+        NodeWalker::new(slf.py(), &slf.into(), include_self, order)
+    }
+
+    /// Return the first descendant node satisfying all of the given criteria, or None.
+    ///
+    /// :param name: the name of the node
+    /// :param attrs: attributes that the node must have
+    /// :param meta: meta fields that the node must have
+    /// :param predicate: a function which must return True for the node
+    /// :param max_depth: the maximum depth to search, where 1 is the children of self
+    #[pyo3(signature = (name=None, *, attrs=None, meta=None, predicate=None, max_depth=None))]
+    fn find(
+        slf: &PyCell<Self>,
+        name: Option<String>,
+        attrs: Option<HashMap<String, String>>,
+        meta: Option<HashMap<String, PyObject>>,
+        predicate: Option<PyObject>,
+        max_depth: Option<usize>,
+    ) -> PyResult<Option<Py<Node>>> {
+        let filter = NodeFilter {
+            name,
+            attrs,
+            meta,
+            predicate,
+        };
+        Ok(filter.find(slf.py(), &slf.into(), max_depth, true)?.pop())
+    }
+
+    /// Return all descendant nodes satisfying all of the given criteria, in depth-first order.
+    ///
+    /// :param name: the name of the node
+    /// :param attrs: attributes that the node must have
+    /// :param meta: meta fields that the node must have
+    /// :param predicate: a function which must return True for the node
+    /// :param max_depth: the maximum depth to search, where 1 is the children of self
+    #[pyo3(signature = (name=None, *, attrs=None, meta=None, predicate=None, max_depth=None))]
+    fn find_all(
+        slf: &PyCell<Self>,
+        name: Option<String>,
+        attrs: Option<HashMap<String, String>>,
+        meta: Option<HashMap<String, PyObject>>,
+        predicate: Option<PyObject>,
+        max_depth: Option<usize>,
+    ) -> PyResult<Vec<Py<Node>>> {
+        let filter = NodeFilter {
+            name,
+            attrs,
+            meta,
+            predicate,
+        };
+        filter.find(slf.py(), &slf.into(), max_depth, false)
+    }
+
+    /// Return all descendant nodes matching a CSS-like selector, in depth-first order.
+    ///
+    /// :param selector: e.g. `heading[level=2] > text`
+    fn select(slf: &PyCell<Self>, selector: &str) -> PyResult<Vec<Py<Node>>> {
+        SelectorList::parse(selector)?.select(slf.py(), &slf.into(), false)
+    }
+
+    /// Return the first descendant node matching a CSS-like selector, or None.
+    ///
+    /// :param selector: e.g. `heading[level=2] > text`
+    fn select_one(slf: &PyCell<Self>, selector: &str) -> PyResult<Option<Py<Node>>> {
+        Ok(SelectorList::parse(selector)?
+            .select(slf.py(), &slf.into(), true)?
+            .pop())
+    }
+
+    /// Return the concatenated text of the node and its descendants,
+    /// i.e. the `content` of text and code nodes, with line breaks as newlines.
+    ///
+    /// This is similar to `textContent` in the DOM; for a readable plain text rendering
+    /// of a document, see `MarkdownIt.render_text`.
+    fn text_content(&self, py: Python) -> String {
+        let mut text = String::new();
+        self.collect_text(py, &mut text);
+        text
+    }
+
     /// create a pretty string representation of the node
     ///
     /// :param attrs: whether to include attributes in the output
@@ -160,9 +661,9 @@ impl Node {
     }
 }
 
-/// Take a markdown_it::Node and return a Python compatible Node
+/// Convert a markdown-it node (without its children) to a Python node,
+/// using the conversion registered for its type, or a generic fallback.
 pub fn create_node(py: Python, node: &markdown_it::Node) -> Node {
-    // default to a node with the same name as the markdown_it::Node
     let mut py_node = Node::new("unknown");
     py_node._rust_path = Some(node.name().to_string());
 
@@ -174,264 +675,9 @@ pub fn create_node(py: Python, node: &markdown_it::Node) -> Node {
         py_node.srcmap = Some(srcmap.get_byte_offsets());
     }
 
-    if node.cast::<markdown_it::parser::core::Root>().is_some() {
-        py_node.name = "root".to_string();
-    } else if let Some(node_value) = node.cast::<markdown_it::parser::inline::Text>() {
-        py_node.name = "text".to_string();
-        py_node.add_data("content", node_value.content.to_string().into_py(py));
-    } else if let Some(node_value) = node.cast::<markdown_it::parser::inline::TextSpecial>() {
-        py_node.name = "text_special".to_string();
-        py_node.add_data("content", node_value.content.to_string().into_py(py));
-        py_node.add_data("markup", node_value.markup.to_string().into_py(py));
-        py_node.add_data("info", node_value.info.into_py(py));
-    } else if node
-        .cast::<markdown_it::plugins::cmark::block::blockquote::Blockquote>()
-        .is_some()
-    {
-        py_node.name = "blockquote".to_string();
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::block::code::CodeBlock>()
-    {
-        py_node.name = "code_block".to_string();
-        py_node.add_data("content", node_value.content.to_string().into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::block::fence::CodeFence>()
-    {
-        py_node.name = "fence".to_string();
-        py_node.add_data("info", node_value.info.to_string().into_py(py));
-        py_node.add_data("marker", node_value.marker.into_py(py));
-        py_node.add_data("marker_len", node_value.marker_len.into_py(py));
-        py_node.add_data("content", node_value.content.to_string().into_py(py));
-        py_node.add_data("lang_prefix", node_value.lang_prefix.into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::block::heading::ATXHeading>()
-    {
-        py_node.name = "heading".to_string();
-        py_node.add_data("level", node_value.level.into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::block::hr::ThematicBreak>()
-    {
-        py_node.name = "hr".to_string();
-        py_node.add_data("marker", node_value.marker.into_py(py));
-        py_node.add_data("marker_len", node_value.marker_len.into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::block::lheading::SetextHeader>()
-    {
-        py_node.name = "lheading".to_string();
-        py_node.add_data("level", node_value.level.into_py(py));
-        py_node.add_data("marker", node_value.marker.into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::block::list::BulletList>()
-    {
-        py_node.name = "bullet_list".to_string();
-        py_node.add_data("marker", node_value.marker.into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::block::list::OrderedList>()
-    {
-        py_node.name = "ordered_list".to_string();
-        py_node.add_data("start", node_value.start.into_py(py));
-        py_node.add_data("marker", node_value.marker.into_py(py));
-    } else if node
-        .cast::<markdown_it::plugins::cmark::block::list::ListItem>()
-        .is_some()
-    {
-        py_node.name = "list_item".to_string();
-    } else if node
-        .cast::<markdown_it::plugins::cmark::block::paragraph::Paragraph>()
-        .is_some()
-    {
-        py_node.name = "paragraph".to_string();
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::inline::autolink::Autolink>()
-    {
-        py_node.name = "autolink".to_string();
-        py_node.add_data("url", node_value.url.to_string().into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::inline::backticks::CodeInline>()
-    {
-        py_node.name = "code_inline".to_string();
-        py_node.add_data("marker", node_value.marker.into_py(py));
-        py_node.add_data("marker_len", node_value.marker_len.into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::inline::emphasis::Em>()
-    {
-        py_node.name = "em".to_string();
-        py_node.add_data("marker", node_value.marker.into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::inline::emphasis::Strong>()
-    {
-        py_node.name = "strong".to_string();
-        py_node.add_data("marker", node_value.marker.into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::inline::image::Image>()
-    {
-        py_node.name = "image".to_string();
-        py_node.add_data("url", node_value.url.to_string().into_py(py));
-        if let Some(title) = &node_value.title {
-            py_node.add_data("title", title.to_string().into_py(py));
-        }
-    } else if let Some(node_value) = node.cast::<markdown_it::plugins::cmark::inline::link::Link>()
-    {
-        py_node.name = "link".to_string();
-        py_node.add_data("url", node_value.url.to_string().into_py(py));
-        match &node_value.title {
-            Some(title) => {
-                py_node.add_data("title", title.to_string().into_py(py));
-            }
-            None => {}
-        }
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::cmark::block::reference::Definition>()
-    {
-        py_node.name = "definition".to_string();
-        py_node.add_data("label", node_value.label.to_string().into_py(py));
-        py_node.add_data(
-            "destination",
-            node_value.destination.to_string().into_py(py),
-        );
-        match &node_value.title {
-            Some(title) => {
-                py_node.add_data("title", title.to_string().into_py(py));
-            }
-            None => {}
-        }
-    } else if node
-        .cast::<markdown_it::plugins::cmark::inline::newline::Hardbreak>()
-        .is_some()
-    {
-        py_node.name = "hardbreak".to_string();
-    } else if node
-        .cast::<markdown_it::plugins::cmark::inline::newline::Softbreak>()
-        .is_some()
-    {
-        py_node.name = "softbreak".to_string();
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::html::html_inline::HtmlInline>()
-    {
-        py_node.name = "html_inline".to_string();
-        py_node.add_data("content", node_value.content.to_string().into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::html::html_block::HtmlBlock>()
-    {
-        py_node.name = "html_block".to_string();
-        py_node.add_data("content", node_value.content.to_string().into_py(py));
-    } else if let Some(node_value) = node.cast::<markdown_it::plugins::extra::linkify::Linkified>()
-    {
-        py_node.name = "linkify".to_string();
-        py_node.add_data("url", node_value.url.to_string().into_py(py));
-    } else if let Some(node_value) =
-        node.cast::<markdown_it::plugins::extra::strikethrough::Strikethrough>()
-    {
-        py_node.name = "strikethrough".to_string();
-        py_node.add_data("marker", node_value.marker.into_py(py));
-    } else if let Some(node_value) = node.cast::<markdown_it::plugins::extra::tables::Table>() {
-        py_node.name = "table".to_string();
-        py_node.add_data(
-            "alignments",
-            node_value
-                .alignments
-                .iter()
-                .map(|x| match x {
-                    markdown_it::plugins::extra::tables::ColumnAlignment::None => {
-                        "none".to_string()
-                    }
-                    markdown_it::plugins::extra::tables::ColumnAlignment::Left => {
-                        "left".to_string()
-                    }
-                    markdown_it::plugins::extra::tables::ColumnAlignment::Center => {
-                        "center".to_string()
-                    }
-                    markdown_it::plugins::extra::tables::ColumnAlignment::Right => {
-                        "right".to_string()
-                    }
-                })
-                .collect::<Vec<String>>()
-                .into_py(py),
-        );
-    } else if node
-        .cast::<markdown_it::plugins::extra::tables::TableBody>()
-        .is_some()
-    {
-        py_node.name = "tbody".to_string();
-    } else if node
-        .cast::<markdown_it::plugins::extra::tables::TableRow>()
-        .is_some()
-    {
-        py_node.name = "trow".to_string();
-    } else if node
-        .cast::<markdown_it::plugins::extra::tables::TableCell>()
-        .is_some()
-    {
-        py_node.name = "tcell".to_string();
-    } else if node
-        .cast::<markdown_it::plugins::extra::tables::TableHead>()
-        .is_some()
-    {
-        py_node.name = "thead".to_string();
-    } else if let Some(node_value) = node.cast::<markdown_it_front_matter::FrontMatter>() {
-        py_node.name = "front_matter".to_string();
-        py_node.add_data("content", node_value.content.to_string().into_py(py));
-    } else if let Some(node_value) = node.cast::<markdown_it_tasklist::TodoCheckbox>() {
-        py_node.name = "todo_checkbox".to_string();
-        py_node.add_data("checked", node_value.checked.into_py(py));
-        py_node.add_data("disabled", node_value.disabled.into_py(py));
-    } else if node
-        .cast::<markdown_it_footnote::inline::InlineFootnote>()
-        .is_some()
-    {
-        py_node.name = "footnote_inline".to_string();
-    } else if let Some(node_value) =
-        node.cast::<markdown_it_footnote::references::FootnoteReference>()
-    {
-        py_node.name = "footnote_ref".to_string();
-        py_node.add_data("def_id", node_value.def_id.into_py(py));
-        py_node.add_data("ref_id", node_value.ref_id.into_py(py));
-        match &node_value.label {
-            Some(label) => {
-                py_node.add_data("label", label.into_py(py));
-            }
-            None => {}
-        }
-    } else if let Some(node_value) =
-        node.cast::<markdown_it_footnote::definitions::FootnoteDefinition>()
-    {
-        py_node.name = "footnote_def".to_string();
-        py_node.add_data("def_id", node_value.def_id.into_py(py));
-        py_node.add_data("inline", node_value.inline.into_py(py));
-        match &node_value.label {
-            Some(label) => {
-                py_node.add_data("label", label.into_py(py));
-            }
-            None => {}
-        }
-    } else if node
-        .cast::<markdown_it_footnote::collect::FootnotesContainerNode>()
-        .is_some()
-    {
-        py_node.name = "footnote_container".to_string();
-    } else if let Some(node_value) =
-        node.cast::<markdown_it_footnote::back_refs::FootnoteRefAnchor>()
-    {
-        py_node.name = "footnote_ref_anchor".to_string();
-        py_node.add_data("ref_ids", node_value.ref_ids.to_object(py));
-    } else if let Some(node_value) = node.cast::<markdown_it_heading_anchors::HeadingAnchor>() {
-        py_node.name = "heading_anchor".to_string();
-        py_node.add_data("href", node_value.href.to_object(py));
-        match &node_value.id {
-            Some(id) => {
-                py_node.add_data("id", id.into_py(py));
-            }
-            None => {}
-        }
-    } else if node.cast::<markdown_it_deflist::DefinitionList>().is_some() {
-        py_node.name = "definition_list".to_string();
-    } else if node.cast::<markdown_it_deflist::DefinitionTerm>().is_some() {
-        py_node.name = "definition_term".to_string();
-    } else if node
-        .cast::<markdown_it_deflist::DefinitionDescription>()
-        .is_some()
-    {
-        py_node.name = "definition_description".to_string();
+    match registry().get(node) {
+        Some(node_type) => node_type.convert(py, node, &mut py_node),
+        None => convert_unknown(py, node, &mut py_node),
     }
 
     py_node
@@ -0,0 +1,207 @@
+//! Abbreviations, e.g. `*[HTML]: Hyper Text Markup Language`,
+//! which wrap all matching words in the document in `<abbr title="...">`.
+//!
+//! Like link reference definitions, the definitions are collected by the block parser,
+//! then applied to the text of the document once it has been inline parsed.
+//!
+//! Based on <https://github.com/markdown-it/markdown-it-abbr>
+use std::collections::HashMap;
+
+use markdown_it::common::sourcemap::SourcePos;
+use markdown_it::common::utils::{is_punct_char, unescape_all};
+use markdown_it::parser::block::{BlockRule, BlockState};
+use markdown_it::parser::core::{CoreRule, Root};
+use markdown_it::parser::extset::RootExt;
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::inline::backticks::CodeInline;
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+#[derive(Debug)]
+/// An abbreviation definition, `*[label]: title`
+pub struct AbbreviationDefinition {
+    pub label: String,
+    pub title: String,
+}
+
+impl NodeValue for AbbreviationDefinition {
+    fn render(&self, _: &Node, _: &mut dyn Renderer) {}
+}
+
+#[derive(Debug)]
+/// An abbreviation in the text, with its text as a child
+pub struct Abbreviation {
+    /// The title of its definition
+    pub title: String,
+}
+
+impl NodeValue for Abbreviation {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        let mut attrs = node.attrs.clone();
+        attrs.push(("title", self.title.clone()));
+        fmt.open("abbr", &attrs);
+        fmt.contents(&node.children);
+        fmt.close("abbr");
+    }
+}
+
+#[derive(Debug, Default)]
+/// The abbreviations defined in the document, mapping labels to titles
+struct AbbreviationMap(HashMap<String, String>);
+
+impl RootExt for AbbreviationMap {}
+
+/// Add the `abbr` plugin
+pub fn add(md: &mut MarkdownIt) {
+    md.block.add_rule::<AbbreviationScanner>();
+    md.add_rule::<AbbreviationRule>()
+        .after::<InlineParserRule>();
+}
+
+struct AbbreviationScanner;
+
+impl BlockRule for AbbreviationScanner {
+    fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+        if state.line_indent(state.line) >= state.md.max_indent {
+            return None;
+        }
+        let line = state.get_line(state.line).strip_prefix("*[")?;
+        let mut chars = line.char_indices();
+        let label_end = loop {
+            match chars.next()? {
+                (_, '[') => return None,
+                (end, ']') => break end,
+                (_, '\\') => {
+                    chars.next();
+                }
+                _ => {}
+            }
+        };
+        let title = line[label_end + 1..].strip_prefix(':')?.trim().to_string();
+        let label = unescape_all(&line[..label_end]).into_owned();
+        if label.is_empty() || title.is_empty() {
+            return None;
+        }
+
+        // the first definition of a label takes precedence
+        let abbreviations = state.root_ext.get_or_insert_default::<AbbreviationMap>();
+        abbreviations
+            .0
+            .entry(label.clone())
+            .or_insert_with(|| title.clone());
+
+        let node = Node::new(AbbreviationDefinition { label, title });
+        Some((node, 1))
+    }
+}
+
+/// Wrap the abbreviations in text nodes
+struct AbbreviationRule;
+
+impl AbbreviationRule {
+    /// Whether the character can be next to an abbreviation
+    fn is_boundary(c: Option<char>) -> bool {
+        c.is_none_or(|c| c.is_whitespace() || is_punct_char(c))
+    }
+
+    /// Find the longest abbreviation at the start of the text
+    fn find<'a>(text: &str, labels: &[(&'a str, &'a str)]) -> Option<(&'a str, &'a str)> {
+        labels.iter().copied().find(|(label, _)| {
+            text.starts_with(label) && Self::is_boundary(text[label.len()..].chars().next())
+        })
+    }
+
+    /// Split a text node into text and abbreviations
+    fn split(node: Node, labels: &[(&str, &str)], result: &mut Vec<Node>) {
+        let content = &node.cast::<Text>().unwrap().content;
+        // source positions are only known if the text is the same as the source
+        let start = node
+            .srcmap
+            .map(|srcmap| srcmap.get_byte_offsets())
+            .filter(|(start, end)| end - start == content.len())
+            .map(|(start, _)| start);
+        let srcmap = |from: usize, to: usize| start.map(|s| SourcePos::new(s + from, s + to));
+
+        let mut last = 0;
+        let mut previous = None;
+        let mut found = Vec::new();
+        for (index, c) in content.char_indices() {
+            if index >= last && Self::is_boundary(previous) {
+                if let Some((label, title)) = Self::find(&content[index..], labels) {
+                    found.push((index, label, title));
+                    last = index + label.len();
+                }
+            }
+            previous = Some(c);
+        }
+        if found.is_empty() {
+            result.push(node);
+            return;
+        }
+
+        let mut last = 0;
+        for (index, label, title) in found {
+            if index > last {
+                let mut text = Node::new(Text {
+                    content: content[last..index].to_string(),
+                });
+                text.srcmap = srcmap(last, index);
+                result.push(text);
+            }
+            let end = index + label.len();
+            let mut text = Node::new(Text {
+                content: label.to_string(),
+            });
+            text.srcmap = srcmap(index, end);
+            let mut abbr = Node::new(Abbreviation {
+                title: title.to_string(),
+            });
+            abbr.srcmap = text.srcmap;
+            abbr.children.push(text);
+            result.push(abbr);
+            last = end;
+        }
+        if last < content.len() {
+            let mut text = Node::new(Text {
+                content: content[last..].to_string(),
+            });
+            text.srcmap = srcmap(last, content.len());
+            result.push(text);
+        }
+    }
+
+    fn walk(node: &mut Node, labels: &[(&str, &str)]) {
+        let mut children = Vec::with_capacity(node.children.len());
+        for mut child in std::mem::take(&mut node.children) {
+            if child.is::<Text>() {
+                Self::split(child, labels, &mut children);
+                continue;
+            }
+            // the text of code spans is literal
+            if !child.is::<CodeInline>() {
+                stacker::maybe_grow(64 * 1024, 1024 * 1024, || Self::walk(&mut child, labels));
+            }
+            children.push(child);
+        }
+        node.children = children;
+    }
+}
+
+impl CoreRule for AbbreviationRule {
+    fn run(root: &mut Node, _: &MarkdownIt) {
+        let Some(abbreviations) = root
+            .cast_mut::<Root>()
+            .and_then(|root| root.ext.remove::<AbbreviationMap>())
+        else {
+            return;
+        };
+        // longest first, so that e.g. `HTML5` is matched before `HTML`
+        let mut labels: Vec<(&str, &str)> = abbreviations
+            .0
+            .iter()
+            .map(|(label, title)| (label.as_str(), title.as_str()))
+            .collect();
+        labels.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then(a.0.cmp(b.0)));
+        Self::walk(root, &labels);
+    }
+}
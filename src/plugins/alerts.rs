@@ -0,0 +1,125 @@
+//! GitHub alerts, i.e. block quotes starting with a `[!KIND]` line:
+//!
+//! ```markdown
+//! > [!NOTE]
+//! > Useful information.
+//! ```
+//!
+//! See <https://docs.github.com/en/get-started/writing-on-github/getting-started-with-writing-and-formatting-on-github/basic-writing-and-formatting-syntax#alerts>
+use markdown_it::common::sourcemap::SourcePos;
+use markdown_it::parser::block::builtin::BlockParserRule;
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::parser::inline::InlineRoot;
+use markdown_it::plugins::cmark::block::blockquote::Blockquote;
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+/// The kinds of alert, and their titles
+const KINDS: &[(&str, &str)] = &[
+    ("note", "Note"),
+    ("tip", "Tip"),
+    ("important", "Important"),
+    ("warning", "Warning"),
+    ("caution", "Caution"),
+];
+
+#[derive(Debug)]
+/// An alert, e.g. `> [!NOTE]`
+pub struct Alert {
+    /// The kind of alert, in lowercase, e.g. `note`
+    pub kind: String,
+}
+
+impl NodeValue for Alert {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        let title = KINDS
+            .iter()
+            .find(|(kind, _)| *kind == self.kind)
+            .map_or(self.kind.as_str(), |(_, title)| title);
+        let mut attrs = node.attrs.clone();
+        attrs.push((
+            "class",
+            format!("markdown-alert markdown-alert-{}", self.kind),
+        ));
+        fmt.cr();
+        fmt.open("div", &attrs);
+        fmt.cr();
+        fmt.open("p", &[("class", "markdown-alert-title".to_string())]);
+        fmt.text(title);
+        fmt.close("p");
+        fmt.cr();
+        fmt.contents(&node.children);
+        fmt.cr();
+        fmt.close("div");
+        fmt.cr();
+    }
+}
+
+/// Add the `alerts` plugin
+pub fn add(md: &mut MarkdownIt) {
+    md.add_rule::<AlertRule>()
+        .after::<BlockParserRule>()
+        .before::<InlineParserRule>();
+}
+
+/// Converts block quotes to alerts, before their content is inline parsed
+struct AlertRule;
+
+impl AlertRule {
+    /// Remove the `[!KIND]` line from the start of a block quote, returning the kind
+    fn take_kind(node: &mut Node) -> Option<String> {
+        let paragraph = node.children.first_mut()?;
+        if !paragraph.is::<Paragraph>() || paragraph.children.len() != 1 {
+            return None;
+        }
+        let inline = paragraph.children[0].cast_mut::<InlineRoot>()?;
+        let (first, rest) = inline
+            .content
+            .split_once('\n')
+            .unwrap_or((&inline.content, ""));
+        let kind = first
+            .trim_end()
+            .strip_prefix("[!")?
+            .strip_suffix(']')?
+            .to_lowercase();
+        if !KINDS.iter().any(|(k, _)| *k == kind) {
+            return None;
+        }
+        if rest.trim().is_empty() {
+            // the alert title is the whole paragraph
+            if node.children.len() == 1 {
+                // alerts must have content
+                return None;
+            }
+            node.children.remove(0);
+            return Some(kind);
+        }
+
+        // remove the first line of the paragraph, and its source mapping
+        let cut = first.len() + 1;
+        inline.content = rest.to_string();
+        inline.mapping.remove(0);
+        for (offset, _) in inline.mapping.iter_mut() {
+            *offset -= cut;
+        }
+        if let (Some(srcmap), Some((_, start))) = (paragraph.srcmap, inline.mapping.first()) {
+            paragraph.srcmap = Some(SourcePos::new(*start, srcmap.get_byte_offsets().1));
+        }
+        Some(kind)
+    }
+}
+
+impl CoreRule for AlertRule {
+    fn run(root: &mut Node, _: &MarkdownIt) {
+        // like GitHub, alerts cannot be nested in other blocks
+        for node in root.children.iter_mut() {
+            if !node.is::<Blockquote>() {
+                continue;
+            }
+            if let Some(kind) = Self::take_kind(node) {
+                node.replace(Alert { kind });
+            }
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! Attributes, in the Pandoc / markdown-it-attrs style `{#id .class key=value}`:
+//!
+//! - directly after an inline element, e.g. `[link](url){.class}` or `*em*{#id}`
+//! - at the end of a heading, paragraph or tight list item, e.g. `# Title {#id}`
+//! - at the end of a fenced code block's info string, e.g. ```` ```python {.class} ````
+//! - as a paragraph on its own, after the block it applies to, e.g. a table or list
+//!
+//! Based on <https://mdit-py-plugins.readthedocs.io/en/latest/#attributes>
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+
+use markdown_it::common::sourcemap::SourcePos;
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::extset::MarkdownItExt;
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::parser::inline::{InlineRule, InlineState, Text, TextSpecial};
+use markdown_it::plugins::cmark::block::fence::CodeFence;
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::plugins::cmark::block::list::ListItem;
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use markdown_it::{MarkdownIt, Node, NodeValue};
+use markdown_it_heading_anchors::HeadingAnchor;
+use pyo3::prelude::*;
+
+use super::Options;
+
+#[derive(Debug)]
+struct AttrsSettings {
+    /// The attribute names that are allowed (all if `None`)
+    allowed: Option<Vec<String>>,
+}
+
+impl MarkdownItExt for AttrsSettings {}
+
+/// Add the `attrs` plugin
+pub fn add(md: &mut MarkdownIt, options: &mut Options) -> PyResult<()> {
+    let settings = AttrsSettings {
+        allowed: options.get("allowed_attributes", None)?,
+    };
+    options.finish()?;
+    md.ext.insert(settings);
+    md.inline.add_rule::<AttrsScanner>();
+    md.add_rule::<AttrsRule>()
+        .after::<InlineParserRule>()
+        .before_all();
+    md.add_rule::<HeadingIdRule>().after_all();
+    Ok(())
+}
+
+/// The maximum number of distinct attribute names to intern,
+/// after which attributes with new names are dropped
+const MAX_NAMES: usize = 1000;
+
+/// Node attribute names must be static, so intern the names that are not known in advance
+/// (this leaks one string per distinct name, up to `MAX_NAMES`)
+fn intern(name: &str) -> Option<&'static str> {
+    match name {
+        "id" => Some("id"),
+        "class" => Some("class"),
+        _ => {
+            static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+            let mut names = NAMES.get_or_init(Default::default).lock().unwrap();
+            if let Some(name) = names.get(name) {
+                return Some(name);
+            }
+            if names.len() >= MAX_NAMES {
+                return None;
+            }
+            let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+            names.insert(name);
+            Some(name)
+        }
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')
+}
+
+/// Split an unquoted value from the text that follows it
+fn split_unquoted(text: &str) -> Option<(&str, &str)> {
+    match text.find([' ', '\t', '}'])? {
+        0 => None,
+        end => Some(text.split_at(end)),
+    }
+}
+
+/// Parse `{...}` at the start of the text, returning the attributes and the length consumed
+fn parse_attrs(text: &str) -> Option<(Vec<(String, String)>, usize)> {
+    let mut attrs = Vec::new();
+    let mut rest = text.strip_prefix('{')?;
+    loop {
+        rest = rest.trim_start_matches([' ', '\t']);
+        if let Some(after) = rest.strip_prefix('}') {
+            if attrs.is_empty() {
+                return None;
+            }
+            return Some((attrs, text.len() - after.len()));
+        }
+        let (key, (value, after)) = match rest.chars().next()? {
+            '#' => ("id", split_unquoted(&rest[1..])?),
+            '.' => ("class", split_unquoted(&rest[1..])?),
+            _ => {
+                let end = rest.find(|c| !is_name_char(c))?;
+                let (key, after) = rest.split_at(end);
+                if !key.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+                    return None;
+                }
+                let after = after.strip_prefix('=')?;
+                let value = match after.chars().next()? {
+                    quote @ ('"' | '\'') => {
+                        let end = after[1..].find(quote)? + 1;
+                        (&after[1..end], &after[end + 1..])
+                    }
+                    _ => split_unquoted(after)?,
+                };
+                (key, value)
+            }
+        };
+        if !after.starts_with([' ', '\t', '}']) {
+            return None;
+        }
+        attrs.push((key.to_string(), value.to_string()));
+        rest = after;
+    }
+}
+
+/// Add attributes to a node, joining classes and replacing other values
+fn apply(node: &mut Node, attrs: Vec<(String, String)>, settings: &AttrsSettings) {
+    for (key, value) in attrs {
+        if let Some(allowed) = &settings.allowed {
+            if !allowed.contains(&key) {
+                continue;
+            }
+        }
+        match node.attrs.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) if key == "class" => {
+                existing.push(' ');
+                existing.push_str(&value);
+            }
+            Some((_, existing)) => *existing = value,
+            None => {
+                if let Some(key) = intern(&key) {
+                    node.attrs.push((key, value));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Temporary node for parsed attributes, which is removed once applied to another node
+struct InlineAttrs {
+    attrs: Vec<(String, String)>,
+    /// The source text, used if the attributes cannot be applied
+    raw: String,
+    /// Whether the attributes follow whitespace (or the start of the content)
+    spaced: bool,
+}
+
+impl NodeValue for InlineAttrs {}
+
+struct AttrsScanner;
+
+impl InlineRule for AttrsScanner {
+    const MARKER: char = '{';
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let src = &state.src[state.pos..state.pos_max];
+        let (attrs, len) = parse_attrs(src)?;
+        let spaced = state.src[..state.pos]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        let node = Node::new(InlineAttrs {
+            attrs,
+            raw: src[..len].to_string(),
+            spaced,
+        });
+        Some((node, len))
+    }
+}
+
+/// Apply the parsed attributes to their nodes
+struct AttrsRule;
+
+impl AttrsRule {
+    /// Whether inline attributes at the end of the node's content apply to the node
+    fn takes_trailing(node: &Node) -> bool {
+        node.is::<Paragraph>()
+            || node.is::<ATXHeading>()
+            || node.is::<SetextHeader>()
+            || node.is::<ListItem>()
+    }
+
+    /// Whether inline attributes directly after the node apply to it
+    fn takes_following(node: &Node) -> bool {
+        !(node.is::<Text>()
+            || node.is::<TextSpecial>()
+            || node.is::<Softbreak>()
+            || node.is::<Hardbreak>()
+            || node.is::<InlineAttrs>())
+    }
+
+    /// Whether the node is a paragraph containing only attributes
+    fn is_attrs_paragraph(node: &Node) -> bool {
+        node.is::<Paragraph>() && node.children.len() == 1 && node.children[0].is::<InlineAttrs>()
+    }
+
+    /// Replace attributes that cannot be applied with their source text
+    fn to_text(node: &mut Node) {
+        let value = node.cast_mut::<InlineAttrs>().unwrap();
+        let content = std::mem::take(&mut value.raw);
+        node.replace(Text { content });
+    }
+
+    fn walk(node: &mut Node, settings: &AttrsSettings) {
+        for child in node.children.iter_mut() {
+            stacker::maybe_grow(64 * 1024, 1024 * 1024, || Self::walk(child, settings));
+        }
+
+        // attributes on their own, apply to the previous block
+        let mut index = 0;
+        while index < node.children.len() {
+            if Self::is_attrs_paragraph(&node.children[index]) {
+                if index == 0 {
+                    Self::to_text(&mut node.children[0].children[0]);
+                } else {
+                    let mut paragraph = node.children.remove(index);
+                    let value = paragraph.children[0].cast_mut::<InlineAttrs>().unwrap();
+                    let attrs = std::mem::take(&mut value.attrs);
+                    apply(&mut node.children[index - 1], attrs, settings);
+                    continue;
+                }
+            }
+            index += 1;
+        }
+
+        let mut index = 0;
+        while index < node.children.len() {
+            let Some(value) = node.children[index].cast::<InlineAttrs>() else {
+                index += 1;
+                continue;
+            };
+            let spaced = value.spaced;
+            let last = index + 1 == node.children.len();
+            if !spaced && index > 0 && Self::takes_following(&node.children[index - 1]) {
+                let mut attrs = node.children.remove(index);
+                let attrs = std::mem::take(&mut attrs.cast_mut::<InlineAttrs>().unwrap().attrs);
+                apply(&mut node.children[index - 1], attrs, settings);
+                continue;
+            }
+            if spaced && last && Self::takes_trailing(node) {
+                if index == 0 && node.is::<Paragraph>() {
+                    // left to the parent, see above
+                    break;
+                }
+                let mut attrs = node.children.remove(index);
+                let attrs = std::mem::take(&mut attrs.cast_mut::<InlineAttrs>().unwrap().attrs);
+                Self::trim_end(&mut node.children);
+                apply(node, attrs, settings);
+                break;
+            }
+            Self::to_text(&mut node.children[index]);
+            index += 1;
+        }
+    }
+
+    /// Remove the whitespace before trailing attributes
+    fn trim_end(children: &mut Vec<Node>) {
+        while let Some(last) = children.last_mut() {
+            if last.is::<Softbreak>() || last.is::<Hardbreak>() {
+                children.pop();
+                continue;
+            }
+            if let Some(text) = last.cast_mut::<Text>() {
+                let trimmed = text.content.trim_end().len();
+                if trimmed == 0 {
+                    children.pop();
+                    continue;
+                }
+                let removed = text.content.len() - trimmed;
+                text.content.truncate(trimmed);
+                if let Some(srcmap) = last.srcmap {
+                    let (start, end) = srcmap.get_byte_offsets();
+                    last.srcmap = Some(SourcePos::new(start, end - removed));
+                }
+            }
+            break;
+        }
+    }
+}
+
+impl CoreRule for AttrsRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(settings) = md.ext.get::<AttrsSettings>() else {
+            return;
+        };
+        Self::walk(root, settings);
+
+        // attributes at the end of a fenced code block's info
+        root.walk_mut(|node, _| {
+            let Some(fence) = node.cast_mut::<CodeFence>() else {
+                return;
+            };
+            let Some(start) = fence.info.rfind('{') else {
+                return;
+            };
+            let Some((attrs, len)) = parse_attrs(&fence.info[start..]) else {
+                return;
+            };
+            if start + len != fence.info.trim_end().len() {
+                return;
+            }
+            fence.info.truncate(start);
+            fence.info.truncate(fence.info.trim_end().len());
+            apply(node, attrs, settings);
+        });
+    }
+}
+
+/// Give precedence to `{#id}` over the ids generated by the `heading_anchors` plugin
+pub(crate) struct HeadingIdRule;
+
+impl CoreRule for HeadingIdRule {
+    fn run(root: &mut Node, _: &MarkdownIt) {
+        root.walk_mut(|node, _| {
+            if !(node.is::<ATXHeading>() || node.is::<SetextHeader>()) {
+                return;
+            }
+            let Some(index) = node.attrs.iter().position(|(k, _)| *k == "id") else {
+                return;
+            };
+            let id = node.attrs[index].1.clone();
+            // remove any generated id, added after the explicit one
+            let mut position = 0;
+            node.attrs.retain(|(k, _)| {
+                position += 1;
+                *k != "id" || position == index + 1
+            });
+            for child in node.children.iter_mut() {
+                if let Some(anchor) = child.cast_mut::<HeadingAnchor>() {
+                    anchor.href = id.clone();
+                    anchor.id = None;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,127 @@
+//! Fenced containers, e.g. for admonitions:
+//!
+//! ```markdown
+//! ::: warning Title
+//! *content*
+//! :::
+//! ```
+//!
+//! Containers can be nested, by using a longer fence for the outer container.
+//!
+//! Based on <https://github.com/markdown-it/markdown-it-container>
+use markdown_it::parser::block::{BlockRule, BlockState};
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+const MARKER: char = ':';
+const MIN_MARKERS: usize = 3;
+
+#[derive(Debug)]
+/// A fenced container, `::: name info`
+pub struct Container {
+    /// The first word after the opening fence
+    pub name: String,
+    /// The rest of the opening fence line, after the name
+    pub info: String,
+    pub marker_len: usize,
+}
+
+impl NodeValue for Container {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        let mut attrs = node.attrs.clone();
+        attrs.push(("class", self.name.clone()));
+        fmt.cr();
+        fmt.open("div", &attrs);
+        fmt.cr();
+        fmt.contents(&node.children);
+        fmt.cr();
+        fmt.close("div");
+        fmt.cr();
+    }
+}
+
+/// Add the `container` plugin
+pub fn add(md: &mut MarkdownIt) {
+    md.block.add_rule::<ContainerScanner>();
+}
+
+struct ContainerScanner;
+
+impl ContainerScanner {
+    /// Count the fence markers at the start of a line
+    fn fence_len(state: &BlockState, line: usize) -> Option<usize> {
+        if state.line_indent(line) >= state.md.max_indent {
+            return None;
+        }
+        let len = state
+            .get_line(line)
+            .chars()
+            .take_while(|c| *c == MARKER)
+            .count();
+        (len >= MIN_MARKERS).then_some(len)
+    }
+
+    /// Parse the opening fence, returning the number of markers, the name and the info
+    fn get_header(state: &BlockState) -> Option<(usize, String, String)> {
+        let len = Self::fence_len(state, state.line)?;
+        let params = state.get_line(state.line)[len..].trim();
+        let (name, info) = params
+            .split_once(char::is_whitespace)
+            .unwrap_or((params, ""));
+        if name.is_empty() {
+            return None;
+        }
+        Some((len, name.to_string(), info.trim().to_string()))
+    }
+
+    /// Whether a line closes a container opened with `len` markers
+    fn is_closing(state: &BlockState, line: usize, len: usize) -> bool {
+        match Self::fence_len(state, line) {
+            Some(end_len) => end_len >= len && state.get_line(line)[end_len..].trim().is_empty(),
+            None => false,
+        }
+    }
+}
+
+impl BlockRule for ContainerScanner {
+    fn check(state: &mut BlockState) -> Option<()> {
+        Self::get_header(state).map(|_| ())
+    }
+
+    fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+        let (marker_len, name, info) = Self::get_header(state)?;
+        let start_line = state.line;
+
+        // search for the end of the container,
+        // unclosed containers are closed by the end of the document (or parent)
+        let mut next_line = start_line + 1;
+        let mut closed = false;
+        while next_line < state.line_max {
+            if !state.is_empty(next_line) && state.line_indent(next_line) < 0 {
+                break;
+            }
+            if Self::is_closing(state, next_line, marker_len) {
+                closed = true;
+                break;
+            }
+            next_line += 1;
+        }
+
+        // parse the content, with the container as the parent node
+        let container = Node::new(Container {
+            name,
+            info,
+            marker_len,
+        });
+        let old_node = std::mem::replace(&mut state.node, container);
+        let old_line_max = state.line_max;
+        state.line = start_line + 1;
+        state.line_max = next_line;
+        state.md.block.tokenize(state);
+        state.line = start_line;
+        state.line_max = old_line_max;
+        let node = std::mem::replace(&mut state.node, old_node);
+
+        let end_line = if closed { next_line + 1 } else { next_line };
+        Some((node, end_line - start_line))
+    }
+}
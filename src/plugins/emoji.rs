@@ -0,0 +1,131 @@
+//! Emoji shortcodes, e.g. `:smile:`, using GitHub's shortcodes
+//! (see <https://github.com/github/gemoji>).
+use std::collections::HashMap;
+
+use markdown_it::parser::extset::MarkdownItExt;
+use markdown_it::parser::inline::{InlineRule, InlineState};
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+use pyo3::prelude::*;
+
+use super::Options;
+
+#[derive(Debug)]
+/// An emoji shortcode, e.g. `:smile:`
+pub struct Emoji {
+    /// The shortcode, without colons, e.g. `smile`
+    pub markup: String,
+    /// The emoji text, e.g. `😄`
+    pub content: String,
+    /// The URL of an image for the emoji, rendered instead of the text
+    pub url: Option<String>,
+}
+
+impl NodeValue for Emoji {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        let Some(url) = &self.url else {
+            return fmt.text(&self.content);
+        };
+        let shortcode = format!(":{}:", self.markup);
+        let mut attrs = node.attrs.clone();
+        attrs.push(("class", "emoji".to_string()));
+        attrs.push(("src", url.clone()));
+        attrs.push((
+            "alt",
+            match self.content.is_empty() {
+                true => shortcode.clone(),
+                false => self.content.clone(),
+            },
+        ));
+        attrs.push(("title", shortcode));
+        fmt.self_close("img", &attrs);
+    }
+}
+
+#[derive(Debug)]
+struct EmojiSettings {
+    /// Custom shortcodes, which take precedence over GitHub's
+    shortcodes: HashMap<String, String>,
+    /// A template for image URLs, with `{shortcode}` and `{codepoints}` placeholders
+    image_url: Option<String>,
+}
+
+impl MarkdownItExt for EmojiSettings {}
+
+fn is_shortcode_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-')
+}
+
+/// Add the `emoji` plugin
+pub fn add(md: &mut MarkdownIt, options: &mut Options) -> PyResult<()> {
+    let shortcodes: HashMap<String, String> = options.get("shortcodes", HashMap::new())?;
+    if let Some(name) = shortcodes
+        .keys()
+        .find(|name| name.is_empty() || !name.chars().all(is_shortcode_char))
+    {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "Invalid emoji shortcode: {:?} (must contain only letters, digits, '_', '+' or '-')",
+            name
+        )));
+    }
+    let settings = EmojiSettings {
+        shortcodes,
+        image_url: options.get("image_url", None)?,
+    };
+    options.finish()?;
+    md.ext.insert(settings);
+    md.inline.add_rule::<EmojiScanner>();
+    Ok(())
+}
+
+/// Add the `emoji` plugin, with the default options
+pub fn add_default(md: &mut MarkdownIt) {
+    md.ext.insert(EmojiSettings {
+        shortcodes: HashMap::new(),
+        image_url: None,
+    });
+    md.inline.add_rule::<EmojiScanner>();
+}
+
+struct EmojiScanner;
+
+impl InlineRule for EmojiScanner {
+    const MARKER: char = ':';
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let settings = state.md.ext.get::<EmojiSettings>()?;
+        // shortcodes cannot be part of a word, e.g. `a:b:c`
+        if state.src[..state.pos]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric)
+        {
+            return None;
+        }
+        let src = &state.src[state.pos + 1..state.pos_max];
+        let end = src.find(|c| !is_shortcode_char(c))?;
+        if end == 0 || !src[end..].starts_with(':') {
+            return None;
+        }
+        let name = &src[..end];
+        let content = match settings.shortcodes.get(name) {
+            Some(content) => content.to_string(),
+            None => emojis::get_by_shortcode(name)?.as_str().to_string(),
+        };
+        let url = settings.image_url.as_ref().map(|template| {
+            let codepoints = content
+                .chars()
+                .map(|c| format!("{:x}", c as u32))
+                .collect::<Vec<_>>()
+                .join("-");
+            template
+                .replace("{shortcode}", name)
+                .replace("{codepoints}", &codepoints)
+        });
+        let node = Node::new(Emoji {
+            markup: name.to_string(),
+            content,
+            url,
+        });
+        Some((node, end + 2))
+    }
+}
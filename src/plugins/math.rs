@@ -0,0 +1,354 @@
+//! Math plugins, for TeX between dollars (`$inline$`, `$$display$$`)
+//! and amsmath environments (`\begin{equation}...\end{equation}`).
+//!
+//! Based on <https://mdit-py-plugins.readthedocs.io/en/latest/#math>
+use markdown_it::parser::block::{BlockRule, BlockState};
+use markdown_it::parser::extset::MarkdownItExt;
+use markdown_it::parser::inline::{InlineRule, InlineState};
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+use pyo3::prelude::*;
+
+use super::Options;
+
+/// How math is rendered to HTML
+#[derive(Debug, Clone)]
+pub struct MathHtml {
+    /// The class of the wrapping element (no element is added if empty)
+    pub class: String,
+    /// Output the content as raw HTML (e.g. MathML), rather than escaping it
+    pub passthrough: bool,
+}
+
+impl MathHtml {
+    fn content(&self, content: &str, fmt: &mut dyn Renderer) {
+        if self.passthrough {
+            fmt.text_raw(content);
+        } else {
+            fmt.text(content);
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Inline math, `$...$` (or `$$...$$` for display math)
+pub struct MathInline {
+    pub content: String,
+    pub display: bool,
+    pub html: MathHtml,
+}
+
+impl NodeValue for MathInline {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        if self.html.class.is_empty() {
+            return self.html.content(self.content.trim(), fmt);
+        }
+        let mut attrs = node.attrs.clone();
+        attrs.push(("class", self.html.class.clone()));
+        fmt.open("span", &attrs);
+        self.html.content(self.content.trim(), fmt);
+        fmt.close("span");
+    }
+}
+
+#[derive(Debug)]
+/// A block of math, `$$...$$` or an amsmath environment
+pub struct MathBlock {
+    pub content: String,
+    /// The label of the equation, e.g. `$$...$$ (label)`
+    pub label: Option<String>,
+    /// The amsmath environment, e.g. `equation`
+    pub environment: Option<String>,
+    pub html: MathHtml,
+}
+
+impl NodeValue for MathBlock {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        let mut attrs = node.attrs.clone();
+        let id = self
+            .label
+            .as_ref()
+            .map(|label| label.split_whitespace().collect::<Vec<_>>().join("-"));
+        if let Some(id) = &id {
+            attrs.push(("id", id.clone()));
+        }
+        fmt.cr();
+        let wrap = !self.html.class.is_empty();
+        if wrap {
+            attrs.push(("class", self.html.class.clone()));
+            fmt.open("div", &attrs);
+            fmt.cr();
+            if let Some(id) = &id {
+                let href = vec![
+                    ("href", format!("#{}", id)),
+                    ("class", "mathlabel".to_string()),
+                    ("title", "Permalink to this equation".to_string()),
+                ];
+                fmt.open("a", &href);
+                fmt.text("¶");
+                fmt.close("a");
+                fmt.cr();
+            }
+        }
+        self.html.content(self.content.trim(), fmt);
+        if wrap {
+            fmt.cr();
+            fmt.close("div");
+        }
+        fmt.cr();
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DollarMathSettings {
+    allow_space: bool,
+    allow_digits: bool,
+    double_inline: bool,
+    allow_labels: bool,
+    inline: MathHtml,
+    display: MathHtml,
+    block: MathHtml,
+}
+
+impl MarkdownItExt for DollarMathSettings {}
+
+#[derive(Debug, Clone)]
+struct AmsMathSettings {
+    block: MathHtml,
+}
+
+impl MarkdownItExt for AmsMathSettings {}
+
+/// Add the `dollarmath` plugin, for `$inline$` and `$$display$$` math
+pub fn add_dollarmath(md: &mut MarkdownIt, options: &mut Options) -> PyResult<()> {
+    let passthrough = options.get("passthrough", false)?;
+    let settings = DollarMathSettings {
+        allow_space: options.get("allow_space", true)?,
+        allow_digits: options.get("allow_digits", true)?,
+        double_inline: options.get("double_inline", false)?,
+        allow_labels: options.get("allow_labels", true)?,
+        inline: MathHtml {
+            class: options.get("inline_class", "math inline".to_string())?,
+            passthrough,
+        },
+        display: MathHtml {
+            class: options.get("display_class", "math display".to_string())?,
+            passthrough,
+        },
+        block: MathHtml {
+            class: options.get("block_class", "math block".to_string())?,
+            passthrough,
+        },
+    };
+    options.finish()?;
+    md.ext.insert(settings);
+    md.inline.add_rule::<DollarMathScanner>();
+    md.block.add_rule::<DollarMathBlockScanner>();
+    Ok(())
+}
+
+/// Add the `amsmath` plugin, for top-level amsmath environments
+pub fn add_amsmath(md: &mut MarkdownIt, options: &mut Options) -> PyResult<()> {
+    let settings = AmsMathSettings {
+        block: MathHtml {
+            class: options.get("block_class", "math amsmath".to_string())?,
+            passthrough: options.get("passthrough", false)?,
+        },
+    };
+    options.finish()?;
+    md.ext.insert(settings);
+    md.block.add_rule::<AmsMathScanner>();
+    Ok(())
+}
+
+/// Whether the character at a byte offset is escaped by an odd number of backslashes
+fn is_escaped(text: &str, offset: usize) -> bool {
+    text[..offset]
+        .bytes()
+        .rev()
+        .take_while(|b| *b == b'\\')
+        .count()
+        % 2
+        == 1
+}
+
+struct DollarMathScanner;
+
+impl InlineRule for DollarMathScanner {
+    const MARKER: char = '$';
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let settings = state.md.ext.get::<DollarMathSettings>()?;
+        let src = &state.src[state.pos..state.pos_max];
+        let display = settings.double_inline && src.starts_with("$$");
+        let marker = if display { "$$" } else { "$" };
+        let start = marker.len();
+
+        if !settings.allow_space && src[start..].starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        // find the closing marker
+        let mut end = start;
+        loop {
+            end += src[end..].find(marker)?;
+            if !is_escaped(src, end) {
+                break;
+            }
+            end += 1;
+        }
+        let content = &src[start..end];
+        if content.trim().is_empty()
+            || (!settings.allow_space && content.ends_with(char::is_whitespace))
+            || (!settings.allow_digits
+                && src[end + marker.len()..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            return None;
+        }
+
+        let html = match display {
+            true => settings.display.clone(),
+            false => settings.inline.clone(),
+        };
+        let node = Node::new(MathInline {
+            content: content.to_string(),
+            display,
+            html,
+        });
+        Some((node, end + marker.len()))
+    }
+}
+
+struct DollarMathBlockScanner;
+
+impl DollarMathBlockScanner {
+    /// Find the closing `$$` at the end of a line, returning the content before it and the label
+    fn closing(line: &str, allow_labels: bool) -> Option<(&str, Option<String>)> {
+        let line = line.trim_end();
+        if let Some(content) = line.strip_suffix("$$") {
+            return Some((content, None));
+        }
+        if !allow_labels {
+            return None;
+        }
+        let (content, label) = line.strip_suffix(')')?.rsplit_once("$$")?;
+        let label = label.trim_start().strip_prefix('(')?.trim();
+        if label.is_empty() || label.contains('$') {
+            return None;
+        }
+        Some((content, Some(label.to_string())))
+    }
+}
+
+impl BlockRule for DollarMathBlockScanner {
+    fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+        let settings = state.md.ext.get::<DollarMathSettings>()?;
+        if state.line_indent(state.line) >= state.md.max_indent {
+            return None;
+        }
+        let first = state.get_line(state.line).strip_prefix("$$")?;
+
+        // the closing marker may be on the first line
+        if let Some((content, label)) = Self::closing(first, settings.allow_labels) {
+            let node = Node::new(MathBlock {
+                content: content.to_string(),
+                label,
+                environment: None,
+                html: settings.block.clone(),
+            });
+            return Some((node, 1));
+        }
+
+        let mut next_line = state.line + 1;
+        let (last, label) = loop {
+            if next_line >= state.line_max
+                || (!state.is_empty(next_line) && state.line_indent(next_line) < 0)
+            {
+                // unclosed blocks are not math
+                return None;
+            }
+            if let Some(closing) = Self::closing(state.get_line(next_line), settings.allow_labels) {
+                break closing;
+            }
+            next_line += 1;
+        };
+
+        // the content may start on the first line and end on the last line
+        let mut parts = Vec::new();
+        if !first.trim().is_empty() {
+            parts.push(first.to_string());
+        }
+        if next_line > state.line + 1 {
+            let indent = state.blk_indent;
+            parts.push(state.get_lines(state.line + 1, next_line, indent, false).0);
+        }
+        if !last.trim().is_empty() {
+            parts.push(last.to_string());
+        }
+        let content = parts.join("\n");
+        let node = Node::new(MathBlock {
+            content,
+            label,
+            environment: None,
+            html: settings.block.clone(),
+        });
+        Some((node, next_line + 1 - state.line))
+    }
+}
+
+/// The amsmath environments, see <https://ctan.org/pkg/amsmath>
+const ENVIRONMENTS: &[&str] = &[
+    "equation", "multline", "gather", "align", "alignat", "flalign", "matrix", "pmatrix",
+    "bmatrix", "Bmatrix", "vmatrix", "Vmatrix", "eqnarray",
+];
+
+struct AmsMathScanner;
+
+impl BlockRule for AmsMathScanner {
+    fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+        let settings = state.md.ext.get::<AmsMathSettings>()?;
+        if state.line_indent(state.line) >= state.md.max_indent {
+            return None;
+        }
+        let line = state.get_line(state.line);
+        let (environment, _) = line.strip_prefix("\\begin{")?.split_once('}')?;
+        let name = environment.strip_suffix('*').unwrap_or(environment);
+        if !ENVIRONMENTS.contains(&name) {
+            return None;
+        }
+        let end_marker = format!("\\end{{{}}}", environment);
+
+        let mut content = line.to_string();
+        let mut next_line = state.line;
+        let mut search_from = 0;
+        loop {
+            if let Some(index) = content[search_from..].find(&end_marker) {
+                let end = search_from + index + end_marker.len();
+                // nothing may follow the environment on its last line
+                if !content[end..].trim().is_empty() {
+                    return None;
+                }
+                content.truncate(end);
+                break;
+            }
+            // the end marker cannot span a line break
+            search_from = content.len();
+            next_line += 1;
+            if next_line >= state.line_max
+                || (!state.is_empty(next_line) && state.line_indent(next_line) < 0)
+            {
+                return None;
+            }
+            content.push('\n');
+            let indent = state.blk_indent;
+            content.push_str(&state.get_lines(next_line, next_line + 1, indent, false).0);
+        }
+
+        let node = Node::new(MathBlock {
+            content,
+            label: None,
+            environment: Some(environment.to_string()),
+            html: settings.block.clone(),
+        });
+        Some((node, next_line + 1 - state.line))
+    }
+}
@@ -0,0 +1,96 @@
+//! Syntax plugins implemented in this package, rather than in other crates.
+use markdown_it::parser::core::Root;
+use markdown_it::parser::extset::RootExt;
+use markdown_it::Node;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+pub mod abbr;
+pub mod alerts;
+pub mod attrs;
+pub mod container;
+pub mod emoji;
+pub mod math;
+pub mod pairs;
+pub mod toc;
+pub mod wikilinks;
+
+/// Whether a plugin accepts options
+pub fn configurable(name: &str) -> bool {
+    matches!(
+        name,
+        "dollarmath" | "amsmath" | "attrs" | "wikilinks" | "emoji" | "toc"
+    )
+}
+
+/// The options passed to a plugin, as keyword arguments of `MarkdownIt.enable`
+pub struct Options<'py> {
+    plugin: &'py str,
+    dict: Option<&'py PyDict>,
+    used: Vec<&'static str>,
+}
+
+impl<'py> Options<'py> {
+    pub fn new(plugin: &'py str, dict: Option<&'py PyDict>) -> Self {
+        Self {
+            plugin,
+            dict,
+            used: Vec::new(),
+        }
+    }
+
+    /// Get an option, or the default if it was not given
+    pub fn get<T: FromPyObject<'py>>(&mut self, key: &'static str, default: T) -> PyResult<T> {
+        self.used.push(key);
+        match self.dict.and_then(|dict| dict.get_item(key)) {
+            Some(value) => value.extract().map_err(|err| {
+                pyo3::exceptions::PyTypeError::new_err(format!(
+                    "Invalid option '{}' for plugin '{}': {}",
+                    key, self.plugin, err
+                ))
+            }),
+            None => Ok(default),
+        }
+    }
+
+    /// Check that all the given options have been used
+    pub fn finish(&self) -> PyResult<()> {
+        for key in self.dict.map(|dict| dict.keys()).into_iter().flatten() {
+            let key: &str = key.extract()?;
+            if !self.used.contains(&key) {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Unknown option for plugin '{}': {}",
+                    self.plugin, key
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An error raised by Python code called by a plugin whilst parsing,
+/// which is stored in the root node, to be raised once parsing is complete
+#[derive(Debug)]
+struct PluginError(PyErr);
+
+impl RootExt for PluginError {}
+
+/// Record an error raised whilst parsing (only the first error is kept)
+pub fn set_error(root: &mut Node, err: PyErr) {
+    if let Some(root) = root.cast_mut::<Root>() {
+        if !root.ext.contains::<PluginError>() {
+            root.ext.insert(PluginError(err));
+        }
+    }
+}
+
+/// Raise any error recorded whilst parsing
+pub fn take_error(root: &mut Node) -> PyResult<()> {
+    match root
+        .cast_mut::<Root>()
+        .and_then(|root| root.ext.remove::<PluginError>())
+    {
+        Some(PluginError(err)) => Err(err),
+        None => Ok(()),
+    }
+}
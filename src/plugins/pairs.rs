@@ -0,0 +1,162 @@
+//! Inline structures delimited by pairs of markers:
+//! `~subscript~`, `^superscript^`, `==marked==` and `++inserted++`.
+//!
+//! As in markdown-it-sub and markdown-it-sup, subscript and superscript cannot contain
+//! unescaped whitespace, and their content is not parsed for other inline syntax.
+//! Subscript is parsed before strikethrough, but leaves runs of `~~` to it,
+//! so `~~a ~b~ c~~` is `<s>a <sub>b</sub> c</s>`.
+//! Marked and inserted text are parsed like emphasis, as in markdown-it-mark and markdown-it-ins.
+use markdown_it::generics::inline::emph_pair::{self, EmphPairScanner};
+use markdown_it::parser::inline::{InlineRule, InlineState, Text};
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+macro_rules! pair_node {
+    ($(#[$doc:meta])* $name:ident, $tag:literal) => {
+        $(#[$doc])*
+        #[derive(Debug)]
+        pub struct $name {
+            pub marker: char,
+        }
+
+        impl NodeValue for $name {
+            fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+                fmt.open($tag, &node.attrs);
+                fmt.contents(&node.children);
+                fmt.close($tag);
+            }
+        }
+    };
+}
+
+pair_node!(
+    /// Subscript, `~text~`
+    Subscript,
+    "sub"
+);
+pair_node!(
+    /// Superscript, `^text^`
+    Superscript,
+    "sup"
+);
+pair_node!(
+    /// Marked (highlighted) text, `==text==`
+    Mark,
+    "mark"
+);
+pair_node!(
+    /// Inserted text, `++text++`
+    Inserted,
+    "ins"
+);
+
+/// Add the `sub` plugin
+pub fn add_sub(md: &mut MarkdownIt) {
+    md.inline
+        .add_rule::<SubscriptScanner>()
+        .before::<EmphPairScanner<'~', true>>();
+}
+
+/// Add the `sup` plugin
+pub fn add_sup(md: &mut MarkdownIt) {
+    // after other rules starting with `^`, e.g. inline footnotes `^[note]`
+    md.inline.add_rule::<SuperscriptScanner>().after_all();
+}
+
+/// Add the `mark` plugin
+pub fn add_mark(md: &mut MarkdownIt) {
+    emph_pair::add_with::<'=', 2, true>(md, || Node::new(Mark { marker: '=' }));
+}
+
+/// Add the `ins` plugin
+pub fn add_ins(md: &mut MarkdownIt) {
+    emph_pair::add_with::<'+', 2, true>(md, || Node::new(Inserted { marker: '+' }));
+}
+
+/// Whether the text contains whitespace that is not escaped by a backslash
+fn has_unescaped_whitespace(text: &str) -> bool {
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if c.is_whitespace() => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Remove the backslashes from escaped punctuation and spaces
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\'
+            && chars
+                .peek()
+                .is_some_and(|n| n.is_ascii_punctuation() || *n == ' ')
+        {
+            continue;
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Scan `{marker}content{marker}`, with the content as a single text node
+fn scan(state: &mut InlineState, marker: char, mut node: Node) -> Option<(Node, usize)> {
+    let start = state.pos;
+    // runs of markers, e.g. `~~`, are left to other rules
+    let mut chars = state.src[start..state.pos_max].chars();
+    if chars.next()? != marker || chars.next()? == marker || state.src[..start].ends_with(marker) {
+        return None;
+    }
+
+    // find the closing marker, skipping other inline syntax, e.g. code spans
+    state.pos = start + 1;
+    let mut found = false;
+    while state.pos < state.pos_max {
+        if state.src[state.pos..].starts_with(marker) {
+            found = true;
+            break;
+        }
+        state.md.inline.skip_token(state);
+    }
+    let end = state.pos;
+    state.pos = start;
+    if !found || end == start + 1 {
+        return None;
+    }
+    let content = &state.src[start + 1..end];
+    if has_unescaped_whitespace(content) {
+        return None;
+    }
+
+    let mut text = Node::new(Text {
+        content: unescape(content),
+    });
+    text.srcmap = state.get_map(start + 1, end);
+    node.children.push(text);
+    Some((node, end + 1 - start))
+}
+
+struct SubscriptScanner;
+
+impl InlineRule for SubscriptScanner {
+    const MARKER: char = '~';
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        scan(state, '~', Node::new(Subscript { marker: '~' }))
+    }
+}
+
+struct SuperscriptScanner;
+
+impl InlineRule for SuperscriptScanner {
+    const MARKER: char = '^';
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        scan(state, '^', Node::new(Superscript { marker: '^' }))
+    }
+}
@@ -0,0 +1,221 @@
+//! A table of contents, replacing a `[[toc]]` or `[TOC]` paragraph (case insensitive)
+//! with a nested list of links to the headings of the document.
+//!
+//! The links use the ids of the headings, e.g. from the `heading_anchors` or `attrs` plugins,
+//! and headings without an id are given one, in the same way as `heading_anchors`.
+use github_slugger::Slugger;
+use markdown_it::parser::block::builtin::BlockParserRule;
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::extset::MarkdownItExt;
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::parser::inline::{InlineRoot, Text};
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::plugins::cmark::block::list::{BulletList, ListItem, OrderedList};
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::cmark::inline::link::Link;
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+use markdown_it_heading_anchors::HeadingAnchor;
+use pyo3::prelude::*;
+
+use super::attrs::HeadingIdRule;
+use super::Options;
+
+#[derive(Debug)]
+/// A table of contents, containing a (nested) list of links to the headings
+pub struct TableOfContents;
+
+impl NodeValue for TableOfContents {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        let mut attrs = node.attrs.clone();
+        attrs.push(("class", "table-of-contents".to_string()));
+        fmt.cr();
+        fmt.open("nav", &attrs);
+        fmt.contents(&node.children);
+        fmt.close("nav");
+        fmt.cr();
+    }
+}
+
+#[derive(Debug)]
+struct TocSettings {
+    min_level: u8,
+    max_level: u8,
+    ordered: bool,
+}
+
+impl MarkdownItExt for TocSettings {}
+
+/// Add the `toc` plugin
+pub fn add(md: &mut MarkdownIt, options: &mut Options) -> PyResult<()> {
+    let min_level: u8 = options.get("min_level", 1)?;
+    let max_level: u8 = options.get("max_level", 6)?;
+    if !(1..=6).contains(&min_level) || !(min_level..=6).contains(&max_level) {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "Invalid heading levels for plugin 'toc': {}-{} (must be 1 <= min_level <= max_level <= 6)",
+            min_level, max_level
+        )));
+    }
+    let ordered = match options.get("list_type", "ul".to_string())?.as_str() {
+        "ul" => false,
+        "ol" => true,
+        other => {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Invalid option 'list_type' for plugin 'toc': {:?} (must be 'ul' or 'ol')",
+                other
+            )))
+        }
+    };
+    options.finish()?;
+    md.ext.insert(TocSettings {
+        min_level,
+        max_level,
+        ordered,
+    });
+    md.add_rule::<TocPlaceholderRule>()
+        .after::<BlockParserRule>()
+        .before::<InlineParserRule>();
+    md.add_rule::<TocRule>()
+        .after::<HeadingIdRule>()
+        .after_all();
+    Ok(())
+}
+
+/// Replace the placeholder paragraphs, before they are inline parsed
+/// (so that `[[toc]]` is not parsed as e.g. a wiki link)
+struct TocPlaceholderRule;
+
+impl TocPlaceholderRule {
+    fn is_placeholder(node: &Node) -> bool {
+        if !node.is::<Paragraph>() || node.children.len() != 1 {
+            return false;
+        }
+        let Some(inline) = node.children[0].cast::<InlineRoot>() else {
+            return false;
+        };
+        let content = inline.content.trim();
+        content.eq_ignore_ascii_case("[[toc]]") || content.eq_ignore_ascii_case("[toc]")
+    }
+}
+
+impl CoreRule for TocPlaceholderRule {
+    fn run(root: &mut Node, _: &MarkdownIt) {
+        root.walk_mut(|node, _| {
+            if Self::is_placeholder(node) {
+                node.children.clear();
+                node.replace(TableOfContents);
+            }
+        });
+    }
+}
+
+/// Fill the tables of contents, once the heading ids are known
+struct TocRule;
+
+impl TocRule {
+    fn new_list(settings: &TocSettings) -> Node {
+        match settings.ordered {
+            true => Node::new(OrderedList {
+                start: 1,
+                marker: '.',
+            }),
+            false => Node::new(BulletList { marker: '-' }),
+        }
+    }
+
+    /// The id of a heading, given by an attribute or its anchor
+    fn heading_id(node: &Node) -> Option<String> {
+        if let Some((_, id)) = node.attrs.iter().find(|(key, _)| *key == "id") {
+            return Some(id.clone());
+        }
+        node.children
+            .iter()
+            .find_map(|child| child.cast::<HeadingAnchor>())
+            .map(|anchor| anchor.href.clone())
+    }
+
+    /// Build the nested lists from the headings' levels, ids and text
+    fn build(headings: Vec<(u8, String, String)>, settings: &TocSettings) -> Node {
+        let base = headings.iter().map(|(level, ..)| *level).min().unwrap_or(1);
+        // the lists being built, with their levels
+        let mut stack = vec![(base, Self::new_list(settings))];
+        // close the deepest list, adding it to the last item of its parent
+        fn close(stack: &mut Vec<(u8, Node)>) {
+            let (_, list) = stack.pop().unwrap();
+            let (_, parent) = stack.last_mut().unwrap();
+            parent.children.last_mut().unwrap().children.push(list);
+        }
+
+        for (level, id, text) in headings {
+            while stack.len() > 1 && level < stack.last().unwrap().0 {
+                // after a skipped level, e.g. `##`, `####`, `###`, continue the same list
+                if level > stack[stack.len() - 2].0 {
+                    stack.last_mut().unwrap().0 = level;
+                    break;
+                }
+                close(&mut stack);
+            }
+            let (current, list) = stack.last().unwrap();
+            if level > *current && !list.children.is_empty() {
+                stack.push((level, Self::new_list(settings)));
+            }
+            let mut link = Node::new(Link {
+                url: format!("#{}", id),
+                title: None,
+            });
+            link.children.push(Node::new(Text { content: text }));
+            let mut item = Node::new(ListItem);
+            item.children.push(link);
+            stack.last_mut().unwrap().1.children.push(item);
+        }
+        while stack.len() > 1 {
+            close(&mut stack);
+        }
+        stack.pop().unwrap().1
+    }
+}
+
+impl CoreRule for TocRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(settings) = md.ext.get::<TocSettings>() else {
+            return;
+        };
+        let mut has_toc = false;
+        root.walk(|node, _| has_toc |= node.is::<TableOfContents>());
+        if !has_toc {
+            return;
+        }
+
+        let mut slugger = Slugger::default();
+        let mut headings = Vec::new();
+        root.walk_mut(|node, _| {
+            let level = match (node.cast::<ATXHeading>(), node.cast::<SetextHeader>()) {
+                (Some(heading), _) => heading.level,
+                (_, Some(heading)) => heading.level,
+                _ => return,
+            };
+            if level < settings.min_level || level > settings.max_level {
+                return;
+            }
+            let text = node.collect_text();
+            let id = match Self::heading_id(node) {
+                Some(id) => id,
+                None => {
+                    let id = slugger.slug(&text);
+                    node.attrs.push(("id", id.clone()));
+                    id
+                }
+            };
+            headings.push((level, id, text));
+        });
+
+        if headings.is_empty() {
+            return;
+        }
+        root.walk_mut(|node, _| {
+            if node.is::<TableOfContents>() {
+                node.children = vec![Self::build(headings.clone(), settings)];
+            }
+        });
+    }
+}
@@ -0,0 +1,181 @@
+//! Wiki links, e.g. `[[Page]]`, `[[Page#anchor]]` or `[[Page|label]]`,
+//! as used by Obsidian and other personal knowledge bases.
+use markdown_it::generics::inline::full_link::LinkScanner;
+use markdown_it::parser::core::{CoreRule, Root};
+use markdown_it::parser::extset::MarkdownItExt;
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::parser::inline::{InlineRule, InlineState, Text};
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+use pyo3::prelude::*;
+
+use super::Options;
+
+#[derive(Debug)]
+/// A wiki link, `[[target#anchor|label]]`
+pub struct Wikilink {
+    /// The page linked to (empty for a link within the page, `[[#anchor]]`)
+    pub target: String,
+    pub anchor: Option<String>,
+    /// The label, if given after a `|`
+    pub label: Option<String>,
+    /// The resolved URL of the link
+    pub url: String,
+}
+
+impl NodeValue for Wikilink {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        let mut attrs = node.attrs.clone();
+        attrs.push(("class", "wikilink".to_string()));
+        attrs.push(("href", self.url.clone()));
+        fmt.open("a", &attrs);
+        fmt.contents(&node.children);
+        fmt.close("a");
+    }
+}
+
+#[derive(Debug)]
+struct WikilinkSettings {
+    base_url: String,
+    suffix: String,
+    /// A Python callable `(target, anchor) -> str | None`, overriding the default URL
+    resolver: Option<PyObject>,
+}
+
+impl MarkdownItExt for WikilinkSettings {}
+
+/// Add the `wikilinks` plugin
+pub fn add(md: &mut MarkdownIt, options: &mut Options) -> PyResult<()> {
+    let resolver: Option<PyObject> = options.get("resolver", None)?;
+    if let Some(resolver) = &resolver {
+        if !Python::with_gil(|py| resolver.as_ref(py).is_callable()) {
+            return Err(pyo3::exceptions::PyTypeError::new_err(
+                "Invalid option 'resolver' for plugin 'wikilinks': must be callable",
+            ));
+        }
+    }
+    let settings = WikilinkSettings {
+        base_url: options.get("base_url", String::new())?,
+        suffix: options.get("suffix", String::new())?,
+        resolver,
+    };
+    options.finish()?;
+    md.ext.insert(settings);
+    md.inline
+        .add_rule::<WikilinkScanner>()
+        .before::<LinkScanner<false>>();
+    md.add_rule::<WikilinkResolver>()
+        .after::<InlineParserRule>();
+    Ok(())
+}
+
+struct WikilinkScanner;
+
+impl InlineRule for WikilinkScanner {
+    const MARKER: char = '[';
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let settings = state.md.ext.get::<WikilinkSettings>()?;
+        let src = state.src[state.pos..state.pos_max].strip_prefix("[[")?;
+        let end = src.find("]]")?;
+        let content = &src[..end];
+        if content.contains(['[', ']', '\n']) {
+            return None;
+        }
+
+        let (link, label) = match content.split_once('|') {
+            Some((link, label)) => (link.trim(), Some(label.trim())),
+            None => (content.trim(), None),
+        };
+        let (target, anchor) = match link.split_once('#') {
+            Some((target, anchor)) => (target.trim(), Some(anchor.trim())),
+            None => (link, None),
+        };
+        if target.is_empty() && anchor.is_none_or(str::is_empty) {
+            return None;
+        }
+
+        let mut url = match target {
+            "" => String::new(),
+            _ => format!("{}{}{}", settings.base_url, target, settings.suffix),
+        };
+        if let Some(anchor) = anchor {
+            url.push('#');
+            url.push_str(anchor);
+        }
+        let url = state.md.link_formatter.normalize_link(&url);
+        state.md.link_formatter.validate_link(&url)?;
+        let mut node = Node::new(Wikilink {
+            target: target.to_string(),
+            anchor: anchor.map(str::to_string),
+            label: label.map(str::to_string),
+            url,
+        });
+        let display = label.filter(|l| !l.is_empty()).unwrap_or(link);
+        let mut text = Node::new(Text {
+            content: display.to_string(),
+        });
+        // the offset of the displayed text, which is a slice of the content
+        let start = state.pos + 2 + (display.as_ptr() as usize - content.as_ptr() as usize);
+        text.srcmap = state.get_map(start, start + display.len());
+        node.children.push(text);
+        Some((node, end + 4))
+    }
+}
+
+/// Resolve the URLs of wiki links, with the Python resolver (if given)
+struct WikilinkResolver;
+
+impl CoreRule for WikilinkResolver {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(resolver) = md
+            .ext
+            .get::<WikilinkSettings>()
+            .and_then(|s| s.resolver.as_ref())
+        else {
+            return;
+        };
+        // the source, for links that are left as text
+        let source = root
+            .cast::<Root>()
+            .map_or(String::new(), |root| root.content.clone());
+        let mut error = None;
+        Python::with_gil(|py| {
+            root.walk_mut(|node, _| {
+                if error.is_some() {
+                    return;
+                }
+                let Some(link) = node.cast_mut::<Wikilink>() else {
+                    return;
+                };
+                let url = resolver
+                    .call1(py, (link.target.as_str(), link.anchor.as_deref()))
+                    .and_then(|url| url.extract::<Option<String>>(py));
+                let url = match url {
+                    Ok(Some(url)) => md.link_formatter.normalize_link(&url),
+                    Ok(None) => return,
+                    Err(err) => {
+                        error = Some(err);
+                        return;
+                    }
+                };
+                if md.link_formatter.validate_link(&url).is_some() {
+                    link.url = url;
+                    return;
+                }
+                // like links with unsafe URLs, leave the text as it is
+                let content = match node.srcmap {
+                    Some(srcmap) => {
+                        let (start, end) = srcmap.get_byte_offsets();
+                        source.get(start..end).unwrap_or_default().to_string()
+                    }
+                    None => node.collect_text(),
+                };
+                node.children.clear();
+                node.replace(Text { content });
+            });
+        });
+        if let Some(err) = error {
+            super::set_error(root, err);
+        }
+    }
+}
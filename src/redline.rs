@@ -0,0 +1,208 @@
+//! Render the differences between two Markdown sources, as HTML with `<ins>`/`<del>` markup.
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use markdown_it::{Node, NodeValue, Renderer};
+
+use crate::diff::lcs;
+
+#[derive(Debug)]
+/// Content that was inserted (`<ins>`) or deleted (`<del>`)
+struct Redline {
+    tag: &'static str,
+    block: bool,
+}
+
+impl NodeValue for Redline {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        if self.block {
+            fmt.cr();
+        }
+        fmt.open(self.tag, &node.attrs);
+        if self.block {
+            fmt.cr();
+        }
+        fmt.contents(&node.children);
+        if self.block {
+            fmt.cr();
+        }
+        fmt.close(self.tag);
+        if self.block {
+            fmt.cr();
+        }
+    }
+}
+
+/// Render a node without its children, to compare the data of the node itself
+fn render_shell(node: &mut Node) -> String {
+    let children = std::mem::take(&mut node.children);
+    let html = node.render();
+    node.children = children;
+    html
+}
+
+/// Whether a node is a block, based on its rendered HTML
+fn is_block(node: &Node, html: &str) -> bool {
+    html.ends_with('\n') && !node.is::<Softbreak>() && !node.is::<Hardbreak>()
+}
+
+/// Elements which are only allowed within specific parents (e.g. `<li>` in `<ul>`)
+const RESTRICTED_TAGS: &[&str] = &["<li", "<dt", "<dd", "<thead", "<tbody", "<tr", "<th", "<td"];
+
+/// Wrap consecutive runs of inserted or deleted nodes (of the same block/inline kind)
+fn wrap(nodes: Vec<Node>, tag: &'static str, result: &mut Vec<Node>) {
+    let mut current: Option<Node> = None;
+    for node in nodes {
+        let html = node.render();
+        if html.is_empty() {
+            // nothing to mark up, e.g. reference definitions
+            if tag == "ins" {
+                result.push(node);
+            }
+            continue;
+        }
+        let block = is_block(&node, &html);
+        if block && !node.children.is_empty() && RESTRICTED_TAGS.iter().any(|t| html.starts_with(t))
+        {
+            // these elements cannot be wrapped, so mark up their contents instead
+            result.extend(current.take());
+            let mut node = node;
+            let children = std::mem::take(&mut node.children);
+            wrap(children, tag, &mut node.children);
+            result.push(node);
+            continue;
+        }
+        if let Some(wrapper) = current.as_mut() {
+            if wrapper.cast::<Redline>().unwrap().block == block {
+                wrapper.children.push(node);
+                continue;
+            }
+            result.push(current.take().unwrap());
+        }
+        let mut wrapper = Node::new(Redline { tag, block });
+        wrapper.children.push(node);
+        current = Some(wrapper);
+    }
+    result.extend(current);
+}
+
+/// Split text into alternating runs of whitespace and non-whitespace
+fn split_words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut whitespace = None;
+    for (index, c) in text.char_indices() {
+        let is_whitespace = c.is_whitespace();
+        if whitespace.is_some_and(|w| w != is_whitespace) {
+            words.push(&text[start..index]);
+            start = index;
+        }
+        whitespace = Some(is_whitespace);
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// Diff two texts word by word
+fn diff_text(old: &str, new: &str) -> Vec<Node> {
+    fn text(content: &[&str]) -> Node {
+        Node::new(Text {
+            content: content.concat(),
+        })
+    }
+    let mut result = Vec::new();
+    let (words_a, words_b) = (split_words(old), split_words(new));
+    let mut anchors = lcs(&words_a, &words_b, |a, b| a == b);
+    anchors.push((words_a.len(), words_b.len()));
+    let (mut start_a, mut start_b) = (0, 0);
+    for (end_a, end_b) in anchors {
+        if start_a < end_a {
+            wrap(vec![text(&words_a[start_a..end_a])], "del", &mut result);
+        }
+        if start_b < end_b {
+            wrap(vec![text(&words_b[start_b..end_b])], "ins", &mut result);
+        }
+        if end_b < words_b.len() {
+            // merge with a preceding unchanged text
+            match result.last_mut().and_then(|n| n.cast_mut::<Text>()) {
+                Some(previous) => previous.content.push_str(words_b[end_b]),
+                None => result.push(text(&words_b[end_b..end_b + 1])),
+            }
+        }
+        (start_a, start_b) = (end_a + 1, end_b + 1);
+    }
+    result
+}
+
+/// Merge two matched nodes of the same type
+fn merge_pair(mut old: Node, mut new: Node, result: &mut Vec<Node>) {
+    if let (Some(text_a), Some(text_b)) = (old.cast::<Text>(), new.cast::<Text>()) {
+        result.extend(diff_text(&text_a.content, &text_b.content));
+    } else if (!old.children.is_empty() || !new.children.is_empty())
+        && render_shell(&mut old) == render_shell(&mut new)
+    {
+        let children_a = std::mem::take(&mut old.children);
+        let children_b = std::mem::take(&mut new.children);
+        new.children = merge_children(children_a, children_b);
+        result.push(new);
+    } else {
+        wrap(vec![old], "del", result);
+        wrap(vec![new], "ins", result);
+    }
+}
+
+/// Merge two lists of sibling nodes, marking up the differences
+fn merge_children(old: Vec<Node>, new: Vec<Node>) -> Vec<Node> {
+    let html_a: Vec<String> = old.iter().map(|n| n.render()).collect();
+    let html_b: Vec<String> = new.iter().map(|n| n.render()).collect();
+    let mut anchors = lcs(&html_a, &html_b, |a, b| a == b);
+    anchors.push((old.len(), new.len()));
+
+    let names_a: Vec<&str> = old.iter().map(|n| n.name()).collect();
+    let names_b: Vec<&str> = new.iter().map(|n| n.name()).collect();
+    let mut old: Vec<Option<Node>> = old.into_iter().map(Some).collect();
+    let mut new: Vec<Option<Node>> = new.into_iter().map(Some).collect();
+    fn take(nodes: &mut [Option<Node>]) -> Vec<Node> {
+        nodes.iter_mut().map(|n| n.take().unwrap()).collect()
+    }
+
+    let mut result = Vec::new();
+    let (mut start_a, mut start_b) = (0, 0);
+    for (end_a, end_b) in anchors {
+        // within the gaps between identical nodes, match up nodes of the same type
+        let mut pairs: Vec<(usize, usize)> = lcs(
+            &names_a[start_a..end_a],
+            &names_b[start_b..end_b],
+            |a, b| a == b,
+        )
+        .into_iter()
+        .map(|(i, j)| (start_a + i, start_b + j))
+        .collect();
+        pairs.push((end_a, end_b));
+        let (mut i, mut j) = (start_a, start_b);
+        for (pair_a, pair_b) in pairs {
+            wrap(take(&mut old[i..pair_a]), "del", &mut result);
+            wrap(take(&mut new[j..pair_b]), "ins", &mut result);
+            if pair_a < end_a {
+                let (node_a, node_b) = (old[pair_a].take(), new[pair_b].take());
+                merge_pair(node_a.unwrap(), node_b.unwrap(), &mut result);
+            }
+            (i, j) = (pair_a + 1, pair_b + 1);
+        }
+        if end_b < new.len() {
+            result.push(new[end_b].take().unwrap());
+        }
+        (start_a, start_b) = (end_a + 1, end_b + 1);
+    }
+    result
+}
+
+/// Merge the syntax trees of two versions of a document,
+/// marking up inserted and deleted content at block and inline (word) level.
+pub fn merge_trees(mut old: Node, mut new: Node) -> Node {
+    let children_a = std::mem::take(&mut old.children);
+    let children_b = std::mem::take(&mut new.children);
+    new.children = merge_children(children_a, children_b);
+    new
+}
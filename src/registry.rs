@@ -0,0 +1,623 @@
+//! A registry of the conversions from markdown-it node types to Python nodes.
+use markdown_it::common::TypeKey;
+use markdown_it::parser::core::Root;
+use markdown_it::parser::inline::{Text, TextSpecial};
+use markdown_it::plugins::cmark::block::{
+    blockquote::Blockquote, code::CodeBlock, fence::CodeFence, heading::ATXHeading,
+    hr::ThematicBreak, lheading::SetextHeader, list, paragraph::Paragraph, reference::Definition,
+};
+use markdown_it::plugins::cmark::inline::{
+    autolink::Autolink, backticks::CodeInline, emphasis, image::Image, link::Link, newline,
+};
+use markdown_it::plugins::extra::tables::{self, ColumnAlignment};
+use markdown_it::plugins::extra::{linkify::Linkified, strikethrough::Strikethrough};
+use markdown_it::plugins::html::{html_block::HtmlBlock, html_inline::HtmlInline};
+use markdown_it::NodeValue;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::classes::meta_fields;
+use crate::nodes::Node;
+use crate::plugins::{abbr, alerts, container, emoji, math, pairs, toc, wikilinks};
+
+type Convert = Box<dyn Fn(Python, &markdown_it::Node, &mut Node) + Send + Sync>;
+
+/// The conversion of a markdown-it node type
+pub struct NodeType {
+    /// The name of the Python node
+    pub name: &'static str,
+    /// The plugins that produce the node type (empty for the core parser)
+    pub plugins: &'static [&'static str],
+    /// The rust type name of the node type
+    pub rust_path: &'static str,
+    /// Add the data specific to the node type to the Python node
+    convert: Convert,
+}
+
+impl NodeType {
+    pub fn convert(&self, py: Python, node: &markdown_it::Node, py_node: &mut Node) {
+        py_node.name = self.name.to_string();
+        (self.convert)(py, node, py_node);
+    }
+}
+
+#[derive(Default)]
+pub struct Registry {
+    /// The node types, in order of registration
+    types: Vec<NodeType>,
+    index: HashMap<TypeKey, usize>,
+}
+
+impl Registry {
+    /// Register a node type, which has no data
+    pub fn add<T: NodeValue>(
+        &mut self,
+        name: &'static str,
+        plugins: &'static [&'static str],
+    ) -> &mut Self {
+        self.add_with::<T>(name, plugins, |_, _, _| {})
+    }
+
+    /// Register a node type, with a function to add its data to the Python node
+    pub fn add_with<T: NodeValue>(
+        &mut self,
+        name: &'static str,
+        plugins: &'static [&'static str],
+        convert: impl Fn(Python, &T, &mut Node) + Send + Sync + 'static,
+    ) -> &mut Self {
+        let convert: Convert =
+            Box::new(move |py, node, py_node| convert(py, node.cast::<T>().unwrap(), py_node));
+        let key = TypeKey::of::<T>();
+        self.index.insert(key, self.types.len());
+        self.types.push(NodeType {
+            name,
+            plugins,
+            rust_path: key.name,
+            convert,
+        });
+        self
+    }
+
+    /// Iterate over the registered node types, in order of registration
+    pub fn iter(&self) -> impl Iterator<Item = &NodeType> {
+        self.types.iter()
+    }
+
+    /// Get the registered conversion for a node
+    pub fn get(&self, node: &markdown_it::Node) -> Option<&NodeType> {
+        self.index
+            .get(&node.node_type)
+            .map(|index| &self.types[*index])
+    }
+}
+
+/// The registry of all node types known to this package
+pub fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = Registry::default();
+        register_cmark(&mut registry);
+        register_extra(&mut registry);
+        register_plugins(&mut registry);
+        register_local(&mut registry);
+        registry
+    })
+}
+
+fn register_cmark(registry: &mut Registry) {
+    registry
+        .add::<Root>("root", &[])
+        .add_with::<Text>("text", &[], |py, value, node| {
+            node.add_data("content", value.content.to_string().into_py(py));
+        })
+        .add_with::<TextSpecial>(
+            "text_special",
+            &["entity", "escape", "autolink_ext"],
+            |py, value, node| {
+                node.add_data("content", value.content.to_string().into_py(py));
+                node.add_data("markup", value.markup.to_string().into_py(py));
+                node.add_data("info", value.info.into_py(py));
+            },
+        )
+        .add::<Blockquote>("blockquote", &["blockquote"])
+        .add_with::<CodeBlock>("code_block", &["code"], |py, value, node| {
+            node.add_data("content", value.content.to_string().into_py(py));
+        })
+        .add_with::<CodeFence>("fence", &["fence"], |py, value, node| {
+            node.add_data("info", value.info.to_string().into_py(py));
+            node.add_data("marker", value.marker.into_py(py));
+            node.add_data("marker_len", value.marker_len.into_py(py));
+            node.add_data("content", value.content.to_string().into_py(py));
+            node.add_data("lang_prefix", value.lang_prefix.into_py(py));
+        })
+        .add_with::<ATXHeading>("heading", &["heading"], |py, value, node| {
+            node.add_data("level", value.level.into_py(py));
+        })
+        .add_with::<ThematicBreak>("hr", &["hr"], |py, value, node| {
+            node.add_data("marker", value.marker.into_py(py));
+            node.add_data("marker_len", value.marker_len.into_py(py));
+        })
+        .add_with::<SetextHeader>("lheading", &["lheading"], |py, value, node| {
+            node.add_data("level", value.level.into_py(py));
+            node.add_data("marker", value.marker.into_py(py));
+        })
+        .add_with::<list::BulletList>("bullet_list", &["list"], |py, value, node| {
+            node.add_data("marker", value.marker.into_py(py));
+        })
+        .add_with::<list::OrderedList>("ordered_list", &["list"], |py, value, node| {
+            node.add_data("start", value.start.into_py(py));
+            node.add_data("marker", value.marker.into_py(py));
+        })
+        .add::<list::ListItem>("list_item", &["list"])
+        .add::<Paragraph>("paragraph", &["paragraph"])
+        .add_with::<Autolink>(
+            "autolink",
+            &["autolink", "autolink_ext"],
+            |py, value, node| {
+                node.add_data("url", value.url.to_string().into_py(py));
+            },
+        )
+        .add_with::<CodeInline>("code_inline", &["backticks"], |py, value, node| {
+            node.add_data("marker", value.marker.into_py(py));
+            node.add_data("marker_len", value.marker_len.into_py(py));
+        })
+        .add_with::<emphasis::Em>("em", &["emphasis"], |py, value, node| {
+            node.add_data("marker", value.marker.into_py(py));
+        })
+        .add_with::<emphasis::Strong>("strong", &["emphasis"], |py, value, node| {
+            node.add_data("marker", value.marker.into_py(py));
+        })
+        .add_with::<Image>("image", &["image"], |py, value, node| {
+            node.add_data("url", value.url.to_string().into_py(py));
+            if let Some(title) = &value.title {
+                node.add_data("title", title.to_string().into_py(py));
+            }
+        })
+        .add_with::<Link>("link", &["link"], |py, value, node| {
+            node.add_data("url", value.url.to_string().into_py(py));
+            if let Some(title) = &value.title {
+                node.add_data("title", title.to_string().into_py(py));
+            }
+        })
+        .add_with::<Definition>("definition", &["reference"], |py, value, node| {
+            node.add_data("label", value.label.to_string().into_py(py));
+            node.add_data("destination", value.destination.to_string().into_py(py));
+            if let Some(title) = &value.title {
+                node.add_data("title", title.to_string().into_py(py));
+            }
+        })
+        .add::<newline::Hardbreak>("hardbreak", &["newline"])
+        .add::<newline::Softbreak>("softbreak", &["newline"])
+        .add_with::<HtmlInline>("html_inline", &["html_inline"], |py, value, node| {
+            node.add_data("content", value.content.to_string().into_py(py));
+        })
+        .add_with::<HtmlBlock>("html_block", &["html_block"], |py, value, node| {
+            node.add_data("content", value.content.to_string().into_py(py));
+        });
+}
+
+fn register_extra(registry: &mut Registry) {
+    registry
+        .add_with::<Linkified>("linkify", &["linkify"], |py, value, node| {
+            node.add_data("url", value.url.to_string().into_py(py));
+        })
+        .add_with::<Strikethrough>("strikethrough", &["strikethrough"], |py, value, node| {
+            node.add_data("marker", value.marker.into_py(py));
+        })
+        .add_with::<tables::Table>("table", &["table"], |py, value, node| {
+            let alignments: Vec<&str> = value
+                .alignments
+                .iter()
+                .map(|x| match x {
+                    ColumnAlignment::None => "none",
+                    ColumnAlignment::Left => "left",
+                    ColumnAlignment::Center => "center",
+                    ColumnAlignment::Right => "right",
+                })
+                .collect();
+            node.add_data("alignments", alignments.into_py(py));
+        })
+        .add::<tables::TableBody>("tbody", &["table"])
+        .add::<tables::TableRow>("trow", &["table"])
+        .add::<tables::TableCell>("tcell", &["table"])
+        .add::<tables::TableHead>("thead", &["table"]);
+}
+
+fn register_plugins(registry: &mut Registry) {
+    registry
+        .add_with::<markdown_it_front_matter::FrontMatter>(
+            "front_matter",
+            &["front_matter"],
+            |py, value, node| {
+                node.add_data("content", value.content.to_string().into_py(py));
+            },
+        )
+        .add_with::<markdown_it_tasklist::TodoCheckbox>(
+            "todo_checkbox",
+            &["tasklist"],
+            |py, value, node| {
+                node.add_data("checked", value.checked.into_py(py));
+                node.add_data("disabled", value.disabled.into_py(py));
+            },
+        )
+        .add::<markdown_it_footnote::inline::InlineFootnote>("footnote_inline", &["footnote"])
+        .add_with::<markdown_it_footnote::references::FootnoteReference>(
+            "footnote_ref",
+            &["footnote"],
+            |py, value, node| {
+                node.add_data("def_id", value.def_id.into_py(py));
+                node.add_data("ref_id", value.ref_id.into_py(py));
+                if let Some(label) = &value.label {
+                    node.add_data("label", label.into_py(py));
+                }
+            },
+        )
+        .add_with::<markdown_it_footnote::definitions::FootnoteDefinition>(
+            "footnote_def",
+            &["footnote"],
+            |py, value, node| {
+                node.add_data("def_id", value.def_id.into_py(py));
+                node.add_data("inline", value.inline.into_py(py));
+                if let Some(label) = &value.label {
+                    node.add_data("label", label.into_py(py));
+                }
+            },
+        )
+        .add::<markdown_it_footnote::collect::FootnotesContainerNode>(
+            "footnote_container",
+            &["footnote"],
+        )
+        .add_with::<markdown_it_footnote::back_refs::FootnoteRefAnchor>(
+            "footnote_ref_anchor",
+            &["footnote"],
+            |py, value, node| {
+                node.add_data("ref_ids", value.ref_ids.to_object(py));
+            },
+        )
+        .add_with::<markdown_it_heading_anchors::HeadingAnchor>(
+            "heading_anchor",
+            &["heading_anchors"],
+            |py, value, node| {
+                node.add_data("href", value.href.to_object(py));
+                if let Some(id) = &value.id {
+                    node.add_data("id", id.into_py(py));
+                }
+            },
+        )
+        .add::<markdown_it_deflist::DefinitionList>("definition_list", &["deflist"])
+        .add::<markdown_it_deflist::DefinitionTerm>("definition_term", &["deflist"])
+        .add::<markdown_it_deflist::DefinitionDescription>("definition_description", &["deflist"]);
+}
+
+/// Register the node types of the plugins implemented in this package
+fn register_local(registry: &mut Registry) {
+    registry
+        .add_with::<math::MathInline>("math_inline", &["dollarmath"], |py, value, node| {
+            node.add_data("content", value.content.to_string().into_py(py));
+            node.add_data("display", value.display.into_py(py));
+        })
+        .add_with::<math::MathBlock>(
+            "math_block",
+            &["dollarmath", "amsmath"],
+            |py, value, node| {
+                node.add_data("content", value.content.to_string().into_py(py));
+                if let Some(label) = &value.label {
+                    node.add_data("label", label.into_py(py));
+                }
+                if let Some(environment) = &value.environment {
+                    node.add_data("environment", environment.into_py(py));
+                }
+            },
+        )
+        .add_with::<container::Container>("container", &["container"], |py, value, node| {
+            node.add_data("name", value.name.to_string().into_py(py));
+            node.add_data("info", value.info.to_string().into_py(py));
+            node.add_data("marker_len", value.marker_len.into_py(py));
+        })
+        .add_with::<alerts::Alert>("alert", &["alerts"], |py, value, node| {
+            node.add_data("kind", value.kind.to_string().into_py(py));
+        })
+        .add_with::<wikilinks::Wikilink>("wikilink", &["wikilinks"], |py, value, node| {
+            node.add_data("target", value.target.to_string().into_py(py));
+            if let Some(anchor) = &value.anchor {
+                node.add_data("anchor", anchor.into_py(py));
+            }
+            if let Some(label) = &value.label {
+                node.add_data("label", label.into_py(py));
+            }
+            node.add_data("url", value.url.to_string().into_py(py));
+        })
+        .add_with::<emoji::Emoji>("emoji", &["emoji"], |py, value, node| {
+            node.add_data("markup", value.markup.to_string().into_py(py));
+            node.add_data("content", value.content.to_string().into_py(py));
+            if let Some(url) = &value.url {
+                node.add_data("url", url.into_py(py));
+            }
+        })
+        .add_with::<pairs::Subscript>("sub", &["sub"], |py, value, node| {
+            node.add_data("marker", value.marker.into_py(py));
+        })
+        .add_with::<pairs::Superscript>("sup", &["sup"], |py, value, node| {
+            node.add_data("marker", value.marker.into_py(py));
+        })
+        .add_with::<pairs::Mark>("mark", &["mark"], |py, value, node| {
+            node.add_data("marker", value.marker.into_py(py));
+        })
+        .add_with::<pairs::Inserted>("ins", &["ins"], |py, value, node| {
+            node.add_data("marker", value.marker.into_py(py));
+        })
+        .add_with::<abbr::Abbreviation>("abbr", &["abbr"], |py, value, node| {
+            node.add_data("title", value.title.to_string().into_py(py));
+        })
+        .add_with::<abbr::AbbreviationDefinition>(
+            "abbr_definition",
+            &["abbr"],
+            |py, value, node| {
+                node.add_data("label", value.label.to_string().into_py(py));
+                node.add_data("title", value.title.to_string().into_py(py));
+            },
+        )
+        .add::<toc::TableOfContents>("toc", &["toc"]);
+}
+
+/// Return the schema of the node types that can be created from a syntax tree,
+/// as a mapping of node names to the plugins that produce them, their `_rust_path`,
+/// and their meta keys (with the Python type of the value and whether the key is optional).
+#[pyfunction]
+pub fn node_schema<'py>(py: Python<'py>) -> PyResult<&'py PyDict> {
+    let schema = PyDict::new(py);
+    for node_type in registry().iter() {
+        let meta = PyDict::new(py);
+        for field in meta_fields(node_type.name) {
+            let entry = PyDict::new(py);
+            entry.set_item("type", field.py_type)?;
+            entry.set_item("optional", field.optional)?;
+            meta.set_item(field.key, entry)?;
+        }
+        let entry = PyDict::new(py);
+        entry.set_item("plugins", node_type.plugins.to_vec())?;
+        entry.set_item("_rust_path", node_type.rust_path)?;
+        entry.set_item("meta", meta)?;
+        schema.set_item(node_type.name, entry)?;
+    }
+    Ok(schema)
+}
+
+/// Derive a node name from the rust type name, e.g. `foo::SetextHeader` -> `setext_header`
+pub fn fallback_name(rust_path: &str) -> String {
+    let path = rust_path.split('<').next().unwrap_or_default();
+    let ident: Vec<char> = path
+        .rsplit("::")
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .collect();
+    let mut name = String::new();
+    for (index, c) in ident.iter().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            let previous = ident[index - 1];
+            let next_lower = ident.get(index + 1).is_some_and(|n| n.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_lower)
+            {
+                name.push('_');
+            }
+        }
+        name.extend(c.to_lowercase());
+    }
+    name
+}
+
+/// Split the `Debug` output of a struct, e.g. `Foo { a: 1, b: "x" }`, into its fields
+fn debug_fields(debug: &str) -> Vec<(&str, &str)> {
+    let Some(body) = debug
+        .find('{')
+        .filter(|_| debug.ends_with('}'))
+        .map(|start| debug[start + 1..debug.len() - 1].trim())
+    else {
+        return Vec::new();
+    };
+    let mut fields = Vec::new();
+    // the quote of the string or char literal being scanned, if any
+    let mut quote = None;
+    let (mut depth, mut escaped, mut start) = (0, false, 0);
+    for (index, c) in body.char_indices().chain([(body.len(), ',')]) {
+        if let Some(q) = quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == q => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                if let Some((key, value)) = body[start..index].split_once(':') {
+                    fields.push((key.trim(), value.trim()));
+                }
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+/// Unescape the content of a `Debug` formatted string or char literal
+fn unescape(literal: &str) -> String {
+    let mut content = String::new();
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            content.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => content.push('\n'),
+            Some('t') => content.push('\t'),
+            Some('r') => content.push('\r'),
+            Some('0') => content.push('\0'),
+            Some('u') => {
+                let code: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                content.extend(u32::from_str_radix(&code, 16).ok().and_then(char::from_u32));
+            }
+            Some(c) => content.push(c),
+            None => {}
+        }
+    }
+    content
+}
+
+/// Convert a `Debug` formatted value to Python, where it is a simple literal
+fn debug_value(py: Python, value: &str) -> PyObject {
+    if let Some(inner) = value
+        .strip_prefix("Some(")
+        .and_then(|v| v.strip_suffix(')'))
+    {
+        return debug_value(py, inner);
+    }
+    if value == "None" {
+        return py.None();
+    }
+    if let Ok(value) = value.parse::<bool>() {
+        return value.into_py(py);
+    }
+    if let Ok(value) = value.parse::<i64>() {
+        return value.into_py(py);
+    }
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return unescape(&value[1..value.len() - 1]).into_py(py);
+        }
+    }
+    value.into_py(py)
+}
+
+/// Convert a node that has no registered conversion,
+/// exposing the fields of its `Debug` output and its rendered HTML
+/// (unless it has its own `html` field)
+pub fn convert_unknown(py: Python, node: &markdown_it::Node, py_node: &mut Node) {
+    py_node.name = fallback_name(node.name());
+    let debug = format!("{:?}", node.node_value);
+    for (key, value) in debug_fields(&debug) {
+        py_node.add_data(key, debug_value(py, value));
+    }
+    if !py_node.meta.contains_key("html") {
+        py_node.add_data("html", node.render().into_py(py));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A node type with no registered conversion, whose fields are only read by `Debug`
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct CustomBlock {
+        marker: char,
+        info: String,
+        count: usize,
+        flag: Option<bool>,
+        items: Vec<u8>,
+    }
+
+    impl markdown_it::NodeValue for CustomBlock {
+        fn render(&self, _: &markdown_it::Node, fmt: &mut dyn markdown_it::Renderer) {
+            fmt.text(&self.info);
+        }
+    }
+
+    /// A node type with no registered conversion, with its own `html` field
+    #[derive(Debug)]
+    struct CustomHtml {
+        html: String,
+    }
+
+    impl markdown_it::NodeValue for CustomHtml {
+        fn render(&self, _: &markdown_it::Node, fmt: &mut dyn markdown_it::Renderer) {
+            fmt.text_raw(&self.html);
+        }
+    }
+
+    #[test]
+    fn test_fallback_name() {
+        assert_eq!(fallback_name("foo::SetextHeader"), "setext_header");
+        assert_eq!(fallback_name("foo::HTMLBlock"), "html_block");
+        assert_eq!(fallback_name("foo::H2Title"), "h2_title");
+        assert_eq!(fallback_name("foo::Pair<'~', true>"), "pair");
+        assert_eq!(fallback_name("Text"), "text");
+    }
+
+    #[test]
+    fn test_debug_fields() {
+        assert_eq!(
+            debug_fields(r#"Foo { a: 1, b: "x, y", c: Some([1, 2]), d: Bar { e: 'z' } }"#),
+            vec![
+                ("a", "1"),
+                ("b", r#""x, y""#),
+                ("c", "Some([1, 2])"),
+                ("d", "Bar { e: 'z' }"),
+            ]
+        );
+        // quotes in char literals, and escaped quotes in strings
+        assert_eq!(
+            debug_fields(r#"Foo { a: '"', b: '\'', c: "\"}", d: ',' }"#),
+            vec![
+                ("a", "'\"'"),
+                ("b", r"'\''"),
+                ("c", r#""\"}""#),
+                ("d", "','")
+            ]
+        );
+        assert!(debug_fields("Foo").is_empty());
+        assert!(debug_fields("Foo { }").is_empty());
+        assert!(debug_fields("Foo(1, 2)").is_empty());
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r#"a\nb\tc\"d\\e"#), "a\nb\tc\"d\\e");
+        assert_eq!(unescape(r"\'\0"), "'\0");
+        assert_eq!(unescape(r"\u{1f600}x"), "\u{1f600}x");
+    }
+
+    #[test]
+    fn test_convert_unknown() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let node = markdown_it::Node::new(CustomBlock {
+                marker: '"',
+                info: "a, \"b\"\n".to_string(),
+                count: 2,
+                flag: Some(true),
+                items: vec![1, 2],
+            });
+            let mut py_node = Node::new("unknown");
+            convert_unknown(py, &node, &mut py_node);
+            assert_eq!(py_node.name, "custom_block");
+            let meta = |key: &str| py_node.meta[key].as_ref(py).to_string();
+            assert_eq!(meta("marker"), "\"");
+            assert_eq!(meta("info"), "a, \"b\"\n");
+            assert_eq!(meta("count"), "2");
+            assert_eq!(meta("flag"), "True");
+            assert_eq!(meta("items"), "[1, 2]");
+            assert_eq!(meta("html"), "a, &quot;b&quot;\n");
+
+            // a field named `html` is not overwritten by the rendered HTML
+            let node = markdown_it::Node::new(CustomHtml {
+                html: "field".to_string(),
+            });
+            let mut py_node = Node::new("unknown");
+            convert_unknown(py, &node, &mut py_node);
+            assert_eq!(py_node.meta["html"].as_ref(py).to_string(), "field");
+        });
+    }
+}
@@ -0,0 +1,355 @@
+//! A CSS-selector-like query language for the Node tree.
+//!
+//! Supported syntax:
+//!
+//! - `name`: nodes with the given name (`*` matches any name)
+//! - `[key]`: nodes with an attribute or meta field `key`
+//! - `[key=value]`: nodes with an attribute or meta field `key` equal to `value`,
+//!   other operators are `!=`, `^=` (starts with), `$=` (ends with) and `*=` (contains)
+//! - `a b`: nodes matching `b` that are descendants of a node matching `a`
+//! - `a > b`: nodes matching `b` that are direct children of a node matching `a`
+//! - `a, b`: nodes matching either `a` or `b`
+use pyo3::prelude::*;
+use pyo3::types::PyBool;
+
+use crate::nodes::Node;
+
+#[derive(Debug, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Debug)]
+struct Predicate {
+    key: String,
+    value: Option<(Operator, String)>,
+}
+
+#[derive(Debug, Default)]
+struct Compound {
+    name: Option<String>,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug)]
+struct Selector {
+    compounds: Vec<Compound>,
+    /// combinators[i] joins compounds[i] and compounds[i + 1]
+    combinators: Vec<Combinator>,
+}
+
+/// A parsed list of comma-delimited selectors
+#[derive(Debug)]
+pub struct SelectorList {
+    selectors: Vec<Selector>,
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+struct SelectorParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> SelectorParser<'a> {
+    fn skip_whitespace(&mut self) -> bool {
+        let mut skipped = false;
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+            skipped = true;
+        }
+        skipped
+    }
+
+    fn ident(&mut self) -> String {
+        let mut ident = String::new();
+        while let Some(c) = self.chars.peek() {
+            if !is_ident_char(*c) {
+                break;
+            }
+            ident.push(*c);
+            self.chars.next();
+        }
+        ident
+    }
+
+    fn value(&mut self) -> Result<String, String> {
+        match self.chars.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                let quote = *quote;
+                self.chars.next();
+                let mut value = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some('\\') => match self.chars.next() {
+                            Some(c) => value.push(c),
+                            None => return Err("unterminated string".to_string()),
+                        },
+                        Some(c) if c == quote => return Ok(value),
+                        Some(c) => value.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+            }
+            _ => {
+                let mut value = String::new();
+                while let Some(c) = self.chars.peek() {
+                    if *c == ']' || c.is_whitespace() {
+                        break;
+                    }
+                    value.push(*c);
+                    self.chars.next();
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, String> {
+        self.skip_whitespace();
+        let key = self.ident();
+        if key.is_empty() {
+            return Err("expected a key after '['".to_string());
+        }
+        self.skip_whitespace();
+        let operator = match self.chars.next() {
+            Some(']') => return Ok(Predicate { key, value: None }),
+            Some('=') => Operator::Equal,
+            Some(c @ ('!' | '^' | '$' | '*')) => {
+                if self.chars.next() != Some('=') {
+                    return Err(format!("expected '=' after '{}'", c));
+                }
+                match c {
+                    '!' => Operator::NotEqual,
+                    '^' => Operator::StartsWith,
+                    '$' => Operator::EndsWith,
+                    _ => Operator::Contains,
+                }
+            }
+            Some(c) => return Err(format!("unexpected character '{}' in predicate", c)),
+            None => return Err("unterminated predicate".to_string()),
+        };
+        self.skip_whitespace();
+        let value = self.value()?;
+        self.skip_whitespace();
+        if self.chars.next() != Some(']') {
+            return Err("expected ']' to close predicate".to_string());
+        }
+        Ok(Predicate {
+            key,
+            value: Some((operator, value)),
+        })
+    }
+
+    /// Parse a compound selector, returning `None` if no characters are consumed
+    fn compound(&mut self) -> Result<Option<Compound>, String> {
+        let mut compound = Compound::default();
+        let mut universal = false;
+        if self.chars.peek() == Some(&'*') {
+            self.chars.next();
+            universal = true;
+        } else {
+            let name = self.ident();
+            if !name.is_empty() {
+                compound.name = Some(name);
+            }
+        }
+        while self.chars.peek() == Some(&'[') {
+            self.chars.next();
+            compound.predicates.push(self.predicate()?);
+        }
+        if !universal && compound.name.is_none() && compound.predicates.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(compound))
+    }
+
+    fn selector(&mut self) -> Result<Selector, String> {
+        let mut selector = Selector {
+            compounds: Vec::new(),
+            combinators: Vec::new(),
+        };
+        self.skip_whitespace();
+        loop {
+            match self.compound()? {
+                Some(compound) => selector.compounds.push(compound),
+                None => {
+                    return match self.chars.peek() {
+                        Some(c) => Err(format!("unexpected character '{}'", c)),
+                        None => Err("expected a selector".to_string()),
+                    }
+                }
+            }
+            let whitespace = self.skip_whitespace();
+            match self.chars.peek() {
+                None | Some(',') => return Ok(selector),
+                Some('>') => {
+                    self.chars.next();
+                    self.skip_whitespace();
+                    selector.combinators.push(Combinator::Child);
+                }
+                Some(_) if whitespace => selector.combinators.push(Combinator::Descendant),
+                Some(c) => return Err(format!("unexpected character '{}'", c)),
+            }
+        }
+    }
+
+    fn selector_list(&mut self) -> Result<SelectorList, String> {
+        let mut selectors = vec![self.selector()?];
+        while self.chars.next() == Some(',') {
+            selectors.push(self.selector()?);
+        }
+        Ok(SelectorList { selectors })
+    }
+}
+
+impl SelectorList {
+    /// Parse a selector string
+    pub fn parse(selector: &str) -> PyResult<Self> {
+        SelectorParser {
+            chars: selector.chars().peekable(),
+        }
+        .selector_list()
+        .map_err(|msg| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "Invalid selector {:?}: {}",
+                selector, msg
+            ))
+        })
+    }
+
+    /// Whether the node, with the given ancestors (outermost first), matches any selector
+    fn matches(&self, py: Python, node: &Node, ancestors: &[PyRef<Node>]) -> PyResult<bool> {
+        for selector in self.selectors.iter() {
+            if selector.matches_at(py, selector.compounds.len() - 1, node, ancestors)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Return all descendants of `root` matching the selector (in depth-first order),
+    /// or only the first, if `first_only` is set.
+    pub fn select(&self, py: Python, root: &Py<Node>, first_only: bool) -> PyResult<Vec<Py<Node>>> {
+        fn walk_recursive<'py>(
+            py: Python<'py>,
+            selectors: &SelectorList,
+            ancestors: &mut Vec<PyRef<'py, Node>>,
+            results: &mut Vec<Py<Node>>,
+            first_only: bool,
+        ) -> PyResult<()> {
+            let children = ancestors.last().unwrap().children.clone();
+            for child in children.into_iter() {
+                let child: &'py PyCell<Node> = child.into_ref(py);
+                let child_ref = child.borrow();
+                if selectors.matches(py, &child_ref, ancestors)? {
+                    results.push(child.into());
+                    if first_only {
+                        return Ok(());
+                    }
+                }
+                ancestors.push(child_ref);
+                stacker::maybe_grow(64 * 1024, 1024 * 1024, || {
+                    walk_recursive(py, selectors, ancestors, results, first_only)
+                })?;
+                ancestors.pop();
+                if first_only && !results.is_empty() {
+                    return Ok(());
+                }
+            }
+            Ok(())
+        }
+
+        let mut results = Vec::new();
+        let mut ancestors = vec![root.borrow(py)];
+        walk_recursive(py, self, &mut ancestors, &mut results, first_only)?;
+        Ok(results)
+    }
+}
+
+impl Selector {
+    fn matches_at(
+        &self,
+        py: Python,
+        index: usize,
+        node: &Node,
+        ancestors: &[PyRef<Node>],
+    ) -> PyResult<bool> {
+        if !self.compounds[index].matches(py, node)? {
+            return Ok(false);
+        }
+        if index == 0 {
+            return Ok(true);
+        }
+        match self.combinators[index - 1] {
+            Combinator::Child => match ancestors.split_last() {
+                Some((parent, rest)) => self.matches_at(py, index - 1, parent, rest),
+                None => Ok(false),
+            },
+            Combinator::Descendant => {
+                for i in (0..ancestors.len()).rev() {
+                    if self.matches_at(py, index - 1, &ancestors[i], &ancestors[..i])? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
+impl Compound {
+    fn matches(&self, py: Python, node: &Node) -> PyResult<bool> {
+        if let Some(name) = &self.name {
+            if name != &node.name {
+                return Ok(false);
+            }
+        }
+        for predicate in self.predicates.iter() {
+            if !predicate.matches(py, node)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Predicate {
+    fn matches(&self, py: Python, node: &Node) -> PyResult<bool> {
+        let actual = match node.attrs.get(&self.key) {
+            Some(value) => value.clone(),
+            None => match node.meta.get(&self.key) {
+                Some(value) => meta_to_string(py, value)?,
+                None => return Ok(false),
+            },
+        };
+        Ok(match &self.value {
+            None => true,
+            Some((Operator::Equal, value)) => &actual == value,
+            Some((Operator::NotEqual, value)) => &actual != value,
+            Some((Operator::StartsWith, value)) => actual.starts_with(value.as_str()),
+            Some((Operator::EndsWith, value)) => actual.ends_with(value.as_str()),
+            Some((Operator::Contains, value)) => actual.contains(value.as_str()),
+        })
+    }
+}
+
+/// Convert a meta value to a string, for comparison in predicates
+fn meta_to_string(py: Python, value: &PyObject) -> PyResult<String> {
+    let value = value.as_ref(py);
+    if let Ok(value) = value.downcast::<PyBool>() {
+        return Ok(if value.is_true() { "true" } else { "false" }.to_string());
+    }
+    Ok(value.str()?.to_string())
+}
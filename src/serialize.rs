@@ -0,0 +1,357 @@
+//! A compact binary serialization format for Node trees.
+//!
+//! The format is a header (`MAGIC` and `VERSION`), followed by the root node, where each node is:
+//!
+//! - name (identifier)
+//! - rust path (0 for none, or 1 followed by an identifier)
+//! - srcmap (0 for none, or 1 followed by the start and end varints)
+//! - attrs (count varint, followed by key identifiers and value strings)
+//! - meta (count varint, followed by key identifiers and tagged values)
+//! - children (count varint, followed by the nodes)
+//!
+//! Integers are LEB128 varints and strings are length-prefixed UTF-8.
+//! Identifiers (names and keys) are interned: the first occurrence is written as `len << 1`
+//! followed by the string, and subsequent occurrences as `index << 1 | 1`.
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple};
+use std::collections::HashMap;
+
+use crate::classes::into_py_node;
+use crate::nodes::Node;
+
+const MAGIC: &[u8] = b"MDIT";
+const VERSION: u8 = 1;
+
+// tags for meta values
+const TAG_NONE: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_BIG_INT: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_STR: u8 = 6;
+const TAG_BYTES: u8 = 7;
+const TAG_LIST: u8 = 8;
+const TAG_TUPLE: u8 = 9;
+const TAG_DICT: u8 = 10;
+
+/// The maximum nesting of meta values (lists, tuples and dicts),
+/// which also stops recursive values
+const MAX_DEPTH: usize = 200;
+
+#[derive(Default)]
+struct Encoder {
+    buffer: Vec<u8>,
+    identifiers: HashMap<String, usize>,
+}
+
+impl Encoder {
+    fn varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.buffer.push(byte);
+                return;
+            }
+            self.buffer.push(byte | 0x80);
+        }
+    }
+
+    fn bytes(&mut self, value: &[u8]) {
+        self.varint(value.len() as u64);
+        self.buffer.extend_from_slice(value);
+    }
+
+    fn identifier(&mut self, value: &str) {
+        match self.identifiers.get(value) {
+            Some(index) => self.varint((*index as u64) << 1 | 1),
+            None => {
+                self.identifiers
+                    .insert(value.to_string(), self.identifiers.len());
+                self.varint((value.len() as u64) << 1);
+                self.buffer.extend_from_slice(value.as_bytes());
+            }
+        }
+    }
+
+    fn value(&mut self, value: &PyAny, depth: usize) -> PyResult<()> {
+        if depth > MAX_DEPTH {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Cannot serialize meta value nested more than {} deep (or recursive)",
+                MAX_DEPTH
+            )));
+        }
+        if value.is_none() {
+            self.buffer.push(TAG_NONE);
+        } else if let Ok(value) = value.downcast::<PyBool>() {
+            self.buffer
+                .push(if value.is_true() { TAG_TRUE } else { TAG_FALSE });
+        } else if let Ok(value) = value.downcast::<PyLong>() {
+            match value.extract::<i64>() {
+                Ok(value) => {
+                    self.buffer.push(TAG_INT);
+                    // zigzag encoding, so that small negative numbers are also small
+                    self.varint(((value << 1) ^ (value >> 63)) as u64);
+                }
+                Err(_) => {
+                    self.buffer.push(TAG_BIG_INT);
+                    self.bytes(value.str()?.to_str()?.as_bytes());
+                }
+            }
+        } else if let Ok(value) = value.downcast::<PyFloat>() {
+            self.buffer.push(TAG_FLOAT);
+            self.buffer.extend_from_slice(&value.value().to_le_bytes());
+        } else if let Ok(value) = value.downcast::<PyString>() {
+            self.buffer.push(TAG_STR);
+            self.bytes(value.to_str()?.as_bytes());
+        } else if let Ok(value) = value.downcast::<PyBytes>() {
+            self.buffer.push(TAG_BYTES);
+            self.bytes(value.as_bytes());
+        } else if let Ok(value) = value.downcast::<PyList>() {
+            self.buffer.push(TAG_LIST);
+            self.varint(value.len() as u64);
+            for item in value.iter() {
+                self.value(item, depth + 1)?;
+            }
+        } else if let Ok(value) = value.downcast::<PyTuple>() {
+            self.buffer.push(TAG_TUPLE);
+            self.varint(value.len() as u64);
+            for item in value.iter() {
+                self.value(item, depth + 1)?;
+            }
+        } else if let Ok(value) = value.downcast::<PyDict>() {
+            self.buffer.push(TAG_DICT);
+            self.varint(value.len() as u64);
+            for (key, item) in value.iter() {
+                self.value(key, depth + 1)?;
+                self.value(item, depth + 1)?;
+            }
+        } else {
+            return Err(pyo3::exceptions::PyTypeError::new_err(format!(
+                "Cannot serialize meta value of type: {}",
+                value.get_type().name()?
+            )));
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, py: Python, node: &Py<Node>) -> PyResult<()> {
+        let node = node.borrow(py);
+        self.identifier(&node.name);
+        match &node._rust_path {
+            Some(path) => {
+                self.buffer.push(1);
+                self.identifier(path);
+            }
+            None => self.buffer.push(0),
+        }
+        match node.srcmap {
+            Some((start, end)) => {
+                self.buffer.push(1);
+                self.varint(start as u64);
+                self.varint(end as u64);
+            }
+            None => self.buffer.push(0),
+        }
+        self.varint(node.attrs.len() as u64);
+        for (key, value) in node.attrs.iter() {
+            self.identifier(key);
+            self.bytes(value.as_bytes());
+        }
+        self.varint(node.meta.len() as u64);
+        for (key, value) in node.meta.iter() {
+            self.identifier(key);
+            self.value(value.as_ref(py), 0)?;
+        }
+        self.varint(node.children.len() as u64);
+        for child in node.children.iter() {
+            stacker::maybe_grow(64 * 1024, 1024 * 1024, || self.node(py, child))?;
+        }
+        Ok(())
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    position: usize,
+    identifiers: Vec<String>,
+}
+
+fn invalid(message: &str) -> PyErr {
+    pyo3::exceptions::PyValueError::new_err(format!("Invalid node data: {}", message))
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, length: usize) -> PyResult<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+        let value = &self.data[self.position..end];
+        self.position = end;
+        Ok(value)
+    }
+
+    fn byte(&mut self) -> PyResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> PyResult<u64> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint too long"))
+    }
+
+    fn length(&mut self) -> PyResult<usize> {
+        usize::try_from(self.varint()?).map_err(|_| invalid("length too large"))
+    }
+
+    fn string(&mut self) -> PyResult<&'a str> {
+        let length = self.length()?;
+        std::str::from_utf8(self.take(length)?).map_err(|_| invalid("invalid UTF-8"))
+    }
+
+    fn identifier(&mut self) -> PyResult<String> {
+        let value = self.length()?;
+        if value & 1 == 1 {
+            return self
+                .identifiers
+                .get(value >> 1)
+                .cloned()
+                .ok_or_else(|| invalid("unknown identifier"));
+        }
+        let value = std::str::from_utf8(self.take(value >> 1)?)
+            .map_err(|_| invalid("invalid UTF-8"))?
+            .to_string();
+        self.identifiers.push(value.clone());
+        Ok(value)
+    }
+
+    fn flag(&mut self) -> PyResult<bool> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid("invalid flag")),
+        }
+    }
+
+    fn value(&mut self, py: Python, depth: usize) -> PyResult<PyObject> {
+        if depth > MAX_DEPTH {
+            return Err(invalid("meta value nested too deeply"));
+        }
+        let value = match self.byte()? {
+            TAG_NONE => py.None(),
+            TAG_FALSE => false.into_py(py),
+            TAG_TRUE => true.into_py(py),
+            TAG_INT => {
+                let value = self.varint()?;
+                (((value >> 1) as i64) ^ -((value & 1) as i64)).into_py(py)
+            }
+            TAG_BIG_INT => {
+                let value = self.string()?;
+                py.get_type::<PyLong>().call1((value,))?.into()
+            }
+            TAG_FLOAT => {
+                let bytes: [u8; 8] = self.take(8)?.try_into().unwrap();
+                f64::from_le_bytes(bytes).into_py(py)
+            }
+            TAG_STR => self.string()?.into_py(py),
+            TAG_BYTES => {
+                let length = self.length()?;
+                PyBytes::new(py, self.take(length)?).into()
+            }
+            TAG_LIST | TAG_TUPLE | TAG_DICT => {
+                let tag = self.data[self.position - 1];
+                let length = self.length()?;
+                if tag == TAG_DICT {
+                    let dict = PyDict::new(py);
+                    for _ in 0..length {
+                        let key = self.value(py, depth + 1)?;
+                        dict.set_item(key, self.value(py, depth + 1)?)?;
+                    }
+                    dict.into()
+                } else {
+                    let mut items = Vec::new();
+                    for _ in 0..length {
+                        items.push(self.value(py, depth + 1)?);
+                    }
+                    match tag {
+                        TAG_LIST => PyList::new(py, items).into(),
+                        _ => PyTuple::new(py, items).into(),
+                    }
+                }
+            }
+            _ => return Err(invalid("unknown value tag")),
+        };
+        Ok(value)
+    }
+
+    fn node(&mut self, py: Python) -> PyResult<Py<Node>> {
+        let mut node = Node::new(&self.identifier()?);
+        if self.flag()? {
+            node._rust_path = Some(self.identifier()?);
+        }
+        if self.flag()? {
+            let start = self.length()?;
+            node.srcmap = Some((start, self.length()?));
+        }
+        for _ in 0..self.length()? {
+            let key = self.identifier()?;
+            node.attrs.insert(key, self.string()?.to_string());
+        }
+        for _ in 0..self.length()? {
+            let key = self.identifier()?;
+            node.meta.insert(key, self.value(py, 0)?);
+        }
+        let mut children = Vec::new();
+        for _ in 0..self.length()? {
+            children.push(stacker::maybe_grow(64 * 1024, 1024 * 1024, || {
+                self.node(py)
+            })?);
+        }
+        let node = into_py_node(py, node)?;
+        for child in children.iter() {
+            child.borrow_mut(py).parent = Some(node.clone_ref(py));
+        }
+        node.borrow_mut(py).children = children;
+        Ok(node)
+    }
+}
+
+/// Serialize a node and its descendants to bytes
+pub fn to_bytes(py: Python, node: &Py<Node>) -> PyResult<Vec<u8>> {
+    let mut encoder = Encoder::default();
+    encoder.buffer.extend_from_slice(MAGIC);
+    encoder.buffer.push(VERSION);
+    encoder.node(py, node)?;
+    Ok(encoder.buffer)
+}
+
+/// Deserialize a node and its descendants from bytes
+pub fn from_bytes(py: Python, data: &[u8]) -> PyResult<Py<Node>> {
+    if !data.starts_with(MAGIC) {
+        return Err(invalid("missing header"));
+    }
+    let mut decoder = Decoder {
+        data,
+        position: MAGIC.len(),
+        identifiers: Vec::new(),
+    };
+    let version = decoder.byte()?;
+    if version != VERSION {
+        return Err(invalid(&format!("unsupported version {}", version)));
+    }
+    let node = decoder.node(py)?;
+    if decoder.position != data.len() {
+        return Err(invalid("trailing data"));
+    }
+    Ok(node)
+}
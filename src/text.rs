@@ -0,0 +1,587 @@
+//! Render the syntax tree as readable plain text.
+use markdown_it::parser::core::Root;
+use markdown_it::parser::inline::{Text, TextSpecial};
+use markdown_it::plugins::cmark::block::blockquote::Blockquote;
+use markdown_it::plugins::cmark::block::code::CodeBlock;
+use markdown_it::plugins::cmark::block::fence::CodeFence;
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::hr::ThematicBreak;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::plugins::cmark::block::list::{BulletList, ListItem, OrderedList};
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::cmark::block::reference::Definition;
+use markdown_it::plugins::cmark::inline::backticks::CodeInline;
+use markdown_it::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use markdown_it::plugins::extra::tables::{Table, TableBody, TableCell, TableHead, TableRow};
+use markdown_it::plugins::html::html_block::HtmlBlock;
+use markdown_it::plugins::html::html_inline::HtmlInline;
+use markdown_it::Node;
+use pyo3::prelude::*;
+
+use crate::plugins::abbr::AbbreviationDefinition;
+use crate::plugins::alerts::Alert;
+use crate::plugins::container::Container;
+use crate::plugins::emoji::Emoji;
+use crate::plugins::math::{MathBlock, MathInline};
+use crate::plugins::toc::TableOfContents;
+
+/// Whether a node is a block, i.e. it should be separated from its siblings by line breaks
+fn is_block(node: &Node) -> bool {
+    node.is::<Root>()
+        || node.is::<Paragraph>()
+        || node.is::<ATXHeading>()
+        || node.is::<SetextHeader>()
+        || node.is::<CodeBlock>()
+        || node.is::<CodeFence>()
+        || node.is::<ThematicBreak>()
+        || node.is::<Blockquote>()
+        || node.is::<BulletList>()
+        || node.is::<OrderedList>()
+        || node.is::<ListItem>()
+        || node.is::<Definition>()
+        || node.is::<AbbreviationDefinition>()
+        || node.is::<HtmlBlock>()
+        || node.is::<Table>()
+        || node.is::<TableHead>()
+        || node.is::<TableBody>()
+        || node.is::<TableRow>()
+        || node.is::<TableCell>()
+        || node.is::<markdown_it_front_matter::FrontMatter>()
+        || node.is::<markdown_it_footnote::definitions::FootnoteDefinition>()
+        || node.is::<markdown_it_footnote::collect::FootnotesContainerNode>()
+        || node.is::<markdown_it_deflist::DefinitionList>()
+        || node.is::<markdown_it_deflist::DefinitionTerm>()
+        || node.is::<markdown_it_deflist::DefinitionDescription>()
+        || node.is::<MathBlock>()
+        || node.is::<Container>()
+        || node.is::<Alert>()
+        || node.is::<TableOfContents>()
+}
+
+/// A prefix written at the start of each line of a block, e.g. `> ` for blockquotes
+struct Prefix {
+    /// The prefix for the first line, e.g. a list bullet
+    first: String,
+    /// The prefix for subsequent lines
+    rest: String,
+    used: bool,
+}
+
+/// A range of the rendered text, and the range of the source it originates from (byte offsets)
+pub struct Segment {
+    pub text: (usize, usize),
+    pub src: (usize, usize),
+}
+
+/// Plain text renderer
+pub struct TextRenderer {
+    /// Whether to write markup, such as list bullets and blockquote prefixes
+    markup: bool,
+    /// Whether to write inline code and code blocks
+    code: bool,
+    /// The source text, if rendering from the root node
+    source: String,
+    out: String,
+    segments: Vec<Segment>,
+    prefixes: Vec<Prefix>,
+    line_started: bool,
+    /// The number of line breaks to write before the next block
+    pending_breaks: usize,
+}
+
+impl TextRenderer {
+    pub fn new(markup: bool, code: bool) -> Self {
+        Self {
+            markup,
+            code,
+            source: String::new(),
+            out: String::new(),
+            segments: Vec::new(),
+            prefixes: Vec::new(),
+            line_started: false,
+            pending_breaks: 0,
+        }
+    }
+
+    /// Render a syntax tree as plain text,
+    /// returning the text and the segments of it that originate from the source text
+    pub fn render(mut self, node: &Node) -> (String, Vec<Segment>) {
+        if let Some(root) = node.cast::<Root>() {
+            self.source = root.content.clone();
+        }
+        if is_block(node) {
+            self.block(node, 2);
+        } else {
+            self.inline(node);
+        }
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        (self.out, self.segments)
+    }
+
+    /// Write the prefixes for the start of a line, if not already written
+    fn start_line(&mut self) {
+        if self.line_started {
+            return;
+        }
+        self.line_started = true;
+        for prefix in self.prefixes.iter_mut() {
+            if prefix.used {
+                self.out.push_str(&prefix.rest);
+            } else {
+                self.out.push_str(&prefix.first);
+                prefix.used = true;
+            }
+        }
+    }
+
+    fn end_line(&mut self) {
+        if !self.line_started {
+            // blank line, so only write the visible part of the prefixes
+            let prefix: String = self.prefixes.iter().map(|p| p.rest.as_str()).collect();
+            self.out.push_str(prefix.trim_end());
+        }
+        self.out.push('\n');
+        self.line_started = false;
+    }
+
+    fn text(&mut self, text: &str) {
+        self.source_text(text, None);
+    }
+
+    /// Write text, recording the (byte) range of the source it originates from
+    fn source_text(&mut self, text: &str, src: Option<(usize, usize)>) {
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.end_line();
+            }
+            if !line.is_empty() {
+                self.start_line();
+                let start = self.out.len();
+                self.out.push_str(line);
+                if let Some(src) = src {
+                    match self.segments.last_mut() {
+                        // extend the previous segment, if contiguous in both text and source
+                        Some(last) if last.text.1 == start && last.src.1 == src.0 => {
+                            last.text.1 = self.out.len();
+                            last.src.1 = src.1;
+                        }
+                        _ => self.segments.push(Segment {
+                            text: (start, self.out.len()),
+                            src,
+                        }),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Write the lines of code, recording the source range of each line,
+    /// which is found in the source of the block (as the code may be indented or prefixed)
+    fn code_text(&mut self, text: &str, src: Option<(usize, usize)>) {
+        let source = src.and_then(|(start, end)| Some(start).zip(self.source.get(start..end)));
+        let mut ranges = Vec::new();
+        if let Some((start, source)) = source {
+            let mut source_lines = source.split('\n').scan(start, |offset, line| {
+                let line_start = *offset;
+                *offset += line.len() + 1;
+                Some((line_start, line.trim_end_matches('\r')))
+            });
+            for line in text.split('\n') {
+                if line.is_empty() {
+                    ranges.push(None);
+                    continue;
+                }
+                let range = source_lines
+                    .by_ref()
+                    .find(|(_, source_line)| source_line.ends_with(line))
+                    .map(|(line_start, source_line)| {
+                        let end = line_start + source_line.len();
+                        (end - line.len(), end)
+                    });
+                ranges.push(range);
+            }
+        }
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.end_line();
+            }
+            self.source_text(line, ranges.get(index).copied().flatten());
+        }
+    }
+
+    /// Write any pending line breaks, before the start of a block
+    fn start_block(&mut self) {
+        if self.out.is_empty() {
+            self.pending_breaks = 0;
+        }
+        if self.pending_breaks > 0 {
+            self.end_line();
+            for _ in 1..self.pending_breaks {
+                self.end_line();
+            }
+            self.pending_breaks = 0;
+        }
+    }
+
+    /// Mark the end of a block, which should be followed by `breaks` line breaks
+    fn end_block(&mut self, breaks: usize) {
+        self.pending_breaks = self.pending_breaks.max(breaks);
+    }
+
+    /// Render blocks with a prefix on each line
+    fn prefixed(&mut self, first: String, rest: String, nodes: &[Node], breaks: usize) {
+        if !self.markup {
+            self.blocks(nodes, breaks);
+            return;
+        }
+        self.prefixes.push(Prefix {
+            first,
+            rest,
+            used: false,
+        });
+        self.blocks(nodes, breaks);
+        if !self.prefixes.last().unwrap().used {
+            // write the prefix of an empty block, e.g. an empty list item
+            self.start_line();
+            let trimmed = self.out.trim_end_matches(' ').len();
+            self.out.truncate(trimmed);
+        }
+        self.prefixes.pop();
+    }
+
+    /// Render a sequence of nodes, where consecutive inline nodes are rendered as a single block,
+    /// e.g. the contents of a tight list item
+    fn blocks(&mut self, nodes: &[Node], breaks: usize) {
+        let mut in_inline = false;
+        for node in nodes {
+            if in_inline && node.is::<Paragraph>() {
+                // continue the line, e.g. after a task list checkbox
+                self.inlines(&node.children);
+            } else if is_block(node) {
+                if in_inline {
+                    self.end_block(breaks);
+                    in_inline = false;
+                }
+                self.block(node, breaks);
+            } else {
+                if !in_inline {
+                    self.start_block();
+                    in_inline = true;
+                }
+                self.inline(node);
+            }
+        }
+        if in_inline {
+            self.end_block(breaks);
+        }
+    }
+
+    fn block(&mut self, node: &Node, breaks: usize) {
+        if node.is::<Paragraph>()
+            || node.is::<ATXHeading>()
+            || node.is::<SetextHeader>()
+            || node.is::<markdown_it_deflist::DefinitionTerm>()
+        {
+            self.start_block();
+            self.inlines(&node.children);
+            self.end_block(breaks);
+        } else if let Some(value) = node.cast::<CodeBlock>() {
+            if self.code {
+                self.start_block();
+                let src = node.srcmap.map(|s| s.get_byte_offsets());
+                self.code_text(value.content.trim_end_matches('\n'), src);
+                self.end_block(breaks);
+            }
+        } else if let Some(value) = node.cast::<CodeFence>() {
+            if self.code {
+                self.start_block();
+                // the content starts after the opening fence line
+                let src = node
+                    .srcmap
+                    .map(|s| s.get_byte_offsets())
+                    .map(
+                        |(start, end)| match self.source.get(start..end).map(|s| s.find('\n')) {
+                            Some(Some(index)) => (start + index + 1, end),
+                            Some(None) => (end, end),
+                            None => (start, end),
+                        },
+                    );
+                self.code_text(value.content.trim_end_matches('\n'), src);
+                self.end_block(breaks);
+            }
+        } else if let Some(value) = node.cast::<MathBlock>() {
+            if self.code {
+                self.start_block();
+                let src = node.srcmap.map(|s| s.get_byte_offsets());
+                self.source_text(value.content.trim(), src);
+                self.end_block(breaks);
+            }
+        } else if node.is::<ThematicBreak>() {
+            if self.markup {
+                self.start_block();
+                self.text("---");
+                self.end_block(breaks);
+            }
+        } else if node.is::<HtmlBlock>()
+            || node.is::<Definition>()
+            || node.is::<AbbreviationDefinition>()
+            || node.is::<markdown_it_front_matter::FrontMatter>()
+        {
+            // not part of the readable text
+        } else if node.is::<Blockquote>() {
+            self.start_block();
+            self.prefixed("> ".into(), "> ".into(), &node.children, 2);
+            self.end_block(breaks);
+        } else if node.is::<BulletList>() || node.is::<OrderedList>() {
+            // tight lists have no paragraphs in their items
+            let tight = !node
+                .children
+                .iter()
+                .any(|item| item.children.iter().any(|c| c.is::<Paragraph>()));
+            let item_breaks = if tight { 1 } else { 2 };
+            for (index, item) in node.children.iter().enumerate() {
+                let marker = match node.cast::<OrderedList>() {
+                    Some(list) => format!("{}{} ", list.start as usize + index, list.marker),
+                    None => format!("{} ", node.cast::<BulletList>().unwrap().marker),
+                };
+                let indent = " ".repeat(marker.chars().count());
+                self.start_block();
+                self.prefixed(marker, indent, &item.children, item_breaks);
+                self.end_block(item_breaks);
+            }
+            self.end_block(breaks);
+        } else if node.is::<Table>() {
+            self.start_block();
+            for section in node.children.iter() {
+                for row in section.children.iter() {
+                    self.start_block();
+                    for (index, cell) in row.children.iter().enumerate() {
+                        if index > 0 {
+                            self.text(if self.markup { " | " } else { "\t" });
+                        }
+                        self.inlines(&cell.children);
+                    }
+                    self.end_block(1);
+                }
+            }
+            self.end_block(breaks);
+        } else if let Some(value) =
+            node.cast::<markdown_it_footnote::definitions::FootnoteDefinition>()
+        {
+            let marker = match (value.def_id, &value.label) {
+                (Some(def_id), _) => format!("[{}] ", def_id),
+                (None, Some(label)) => format!("[{}] ", label),
+                (None, None) => String::new(),
+            };
+            let indent = " ".repeat(marker.chars().count());
+            self.start_block();
+            self.prefixed(marker, indent, &node.children, 2);
+            self.end_block(breaks);
+        } else if node.is::<markdown_it_deflist::DefinitionDescription>() {
+            self.start_block();
+            self.prefixed("  ".into(), "  ".into(), &node.children, 2);
+            self.end_block(breaks);
+        } else if node.is::<markdown_it_deflist::DefinitionList>() {
+            self.blocks(&node.children, 1);
+            self.end_block(breaks);
+        } else {
+            self.blocks(&node.children, 2);
+            self.end_block(breaks);
+        }
+    }
+
+    fn inlines(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.inline(node);
+        }
+    }
+
+    fn inline(&mut self, node: &Node) {
+        let src = node.srcmap.map(|s| s.get_byte_offsets());
+        if let Some(value) = node.cast::<Text>() {
+            self.source_text(&value.content, src);
+        } else if let Some(value) = node.cast::<TextSpecial>() {
+            self.source_text(&value.content, src);
+        } else if node.is::<Softbreak>() || node.is::<Hardbreak>() {
+            self.text("\n");
+        } else if let Some(value) = node.cast::<markdown_it_tasklist::TodoCheckbox>() {
+            if self.markup {
+                self.text(if value.checked { "[x]" } else { "[ ]" });
+            }
+        } else if let Some(value) =
+            node.cast::<markdown_it_footnote::references::FootnoteReference>()
+        {
+            if self.markup {
+                self.text(&format!("[{}]", value.def_id));
+            }
+        } else if let Some(value) = node.cast::<MathInline>() {
+            if self.code {
+                let marker = if value.display { 2 } else { 1 };
+                let src = src.map(|(start, end)| (start + marker, end - marker));
+                self.source_text(&value.content, src);
+            }
+        } else if let Some(value) = node.cast::<Emoji>() {
+            match value.content.is_empty() {
+                true => self.text(&format!(":{}:", value.markup)),
+                false => self.text(&value.content),
+            }
+        } else if node.is::<CodeInline>() && !self.code {
+            // not part of the prose
+        } else if node.is::<HtmlInline>()
+            || node.is::<markdown_it_footnote::back_refs::FootnoteRefAnchor>()
+            || node.is::<markdown_it_heading_anchors::HeadingAnchor>()
+        {
+            // not part of the readable text
+        } else {
+            self.inlines(&node.children);
+        }
+    }
+}
+
+/// Find the byte offset in `src` corresponding to a byte offset in `text`,
+/// where `text` has been derived from `src`, e.g. by removing escapes.
+fn align(text: &str, src: &str, offset: usize) -> usize {
+    if text == src {
+        return offset;
+    }
+    // greedily match the characters of the text to those of the source
+    let mut src_offset = 0;
+    for (index, c) in text.char_indices() {
+        if index >= offset {
+            break;
+        }
+        if let Some(found) = src[src_offset..].find(c) {
+            src_offset += found + c.len_utf8();
+        }
+    }
+    src_offset
+}
+
+/// Convert a byte offset to a character offset
+fn char_offset(text: &str, offset: usize) -> usize {
+    text[..offset].chars().count()
+}
+
+/// Convert a character offset to a byte offset
+fn byte_offset(text: &str, offset: usize) -> usize {
+    text.char_indices()
+        .nth(offset)
+        .map(|(index, _)| index)
+        .unwrap_or(text.len())
+}
+
+/// Prose text extracted from a Markdown source,
+/// with a mapping from ranges of the text back to the source.
+#[pyclass]
+pub struct Prose {
+    #[pyo3(get)]
+    /// The extracted text
+    text: String,
+    /// The Markdown source
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl Prose {
+    pub fn new(source: &str, node: &Node, code: bool) -> Self {
+        let (text, segments) = TextRenderer::new(false, code).render(node);
+        Self {
+            text,
+            source: source.to_string(),
+            segments,
+        }
+    }
+
+    /// Map a byte offset of the text to a byte offset of the source
+    fn map_offset(&self, offset: usize, is_end: bool) -> usize {
+        let segment = self.segments.iter().find(|s| match is_end {
+            false => s.text.0 <= offset && offset < s.text.1,
+            true => s.text.0 < offset && offset <= s.text.1,
+        });
+        if let Some(segment) = segment {
+            let text = &self.text[segment.text.0..segment.text.1];
+            let src = &self.source[segment.src.0..segment.src.1];
+            return segment.src.0 + align(text, src, offset - segment.text.0);
+        }
+        // the offset is outside of any segment, e.g. in a line break,
+        // so use the nearest segment (inwards of the range)
+        if is_end {
+            self.segments
+                .iter()
+                .rev()
+                .find(|s| s.text.1 <= offset)
+                .map(|s| s.src.1)
+                .unwrap_or(0)
+        } else {
+            self.segments
+                .iter()
+                .find(|s| s.text.0 >= offset)
+                .map(|s| s.src.0)
+                .unwrap_or(self.source.len())
+        }
+    }
+
+    fn to_source_offset(&self, offset: usize, bytes: bool) -> usize {
+        match bytes {
+            true => offset,
+            false => char_offset(&self.source, offset),
+        }
+    }
+}
+
+#[pymethods]
+impl Prose {
+    #[getter]
+    /// The Markdown source
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Return the segments of the text that originate from the source,
+    /// as `(start, end, src_start, src_end)`.
+    ///
+    /// Text offsets are character offsets, and source offsets are character
+    /// offsets, or byte offsets (as used by `Node.srcmap`) if `bytes` is True.
+    #[pyo3(signature = (*, bytes=false))]
+    fn segments(&self, bytes: bool) -> Vec<(usize, usize, usize, usize)> {
+        self.segments
+            .iter()
+            .map(|s| {
+                (
+                    char_offset(&self.text, s.text.0),
+                    char_offset(&self.text, s.text.1),
+                    self.to_source_offset(s.src.0, bytes),
+                    self.to_source_offset(s.src.1, bytes),
+                )
+            })
+            .collect()
+    }
+
+    /// Map a range of the text (as character offsets) to the corresponding range of the source.
+    ///
+    /// Source offsets are character offsets, or byte offsets if `bytes` is True.
+    #[pyo3(signature = (start, end, *, bytes=false))]
+    fn source_span(&self, start: usize, end: usize, bytes: bool) -> PyResult<(usize, usize)> {
+        let length = self.text.chars().count();
+        if start > end || end > length {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Invalid range {}:{} for text of length {}",
+                start, end, length
+            )));
+        }
+        let start = self.map_offset(byte_offset(&self.text, start), false);
+        let end = self
+            .map_offset(byte_offset(&self.text, end), true)
+            .max(start);
+        Ok((
+            self.to_source_offset(start, bytes),
+            self.to_source_offset(end, bytes),
+        ))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Prose(segments={})", self.segments.len())
+    }
+}
@@ -0,0 +1,124 @@
+//! Base classes for visiting and transforming the Node tree,
+//! similar to Python's `ast.NodeVisitor` and `ast.NodeTransformer`.
+use pyo3::exceptions::PyAttributeError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+use crate::nodes::Node;
+
+/// A node visitor base class that walks the tree and calls a
+/// visitor function for every node found.
+///
+/// This class is meant to be subclassed, with the subclass adding visitor methods,
+/// named `visit_` + the node name, e.g. `visit_heading`.
+/// If no visitor method exists for a node, `generic_visit` is called instead,
+/// which visits all children of the node.
+#[pyclass(subclass)]
+pub struct NodeVisitor {
+    /// Cache of the visitor method (from the class) for each node name
+    dispatch: HashMap<String, Option<PyObject>>,
+}
+
+impl NodeVisitor {
+    /// Return the visitor method, defined on the class, for a node name
+    fn visitor_method(slf: &PyCell<Self>, name: &str) -> PyResult<Option<PyObject>> {
+        if let Some(method) = slf.borrow().dispatch.get(name) {
+            return Ok(method.as_ref().map(|m| m.clone_ref(slf.py())));
+        }
+        let method = match slf.get_type().getattr(format!("visit_{}", name).as_str()) {
+            Ok(method) => Some(method.into_py(slf.py())),
+            Err(err) if err.is_instance_of::<PyAttributeError>(slf.py()) => None,
+            Err(err) => return Err(err),
+        };
+        slf.borrow_mut().dispatch.insert(
+            name.to_string(),
+            method.as_ref().map(|m| m.clone_ref(slf.py())),
+        );
+        Ok(method)
+    }
+}
+
+#[pymethods]
+impl NodeVisitor {
+    #[new]
+    fn new() -> Self {
+        Self {
+            dispatch: HashMap::new(),
+        }
+    }
+
+    /// Visit a node, by calling the `visit_<name>` method for it,
+    /// or `generic_visit` if no such method exists.
+    ///
+    /// :returns: the value returned by the visitor method
+    fn visit(slf: &PyCell<Self>, node: Py<Node>) -> PyResult<PyObject> {
+        let py = slf.py();
+        let name = node.borrow(py).name.clone();
+        match NodeVisitor::visitor_method(slf, &name)? {
+            Some(method) => method.call1(py, (slf, node)),
+            None => Ok(slf.call_method1("generic_visit", (node,))?.into_py(py)),
+        }
+    }
+
+    /// Called if no explicit visitor method exists for a node,
+    /// visits all children of the node.
+    fn generic_visit(slf: &PyCell<Self>, node: Py<Node>) -> PyResult<()> {
+        let py = slf.py();
+        let children = node.borrow(py).children.clone();
+        for child in children {
+            slf.call_method1("visit", (child,))?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of visiting a node with a `NodeTransformer`
+#[derive(FromPyObject)]
+enum Transformed {
+    Single(Py<Node>),
+    Multiple(Vec<Py<Node>>),
+}
+
+/// A `NodeVisitor` subclass that walks the tree and allows modification of nodes.
+///
+/// The return value of the visitor methods is used to replace the visited node:
+/// if it is `None` the node is removed, if it is a node the node is replaced by it,
+/// and if it is a list of nodes the node is replaced by all of them.
+#[pyclass(extends=NodeVisitor, subclass)]
+pub struct NodeTransformer {}
+
+#[pymethods]
+impl NodeTransformer {
+    #[new]
+    fn new() -> (Self, NodeVisitor) {
+        (Self {}, NodeVisitor::new())
+    }
+
+    /// Called if no explicit visitor method exists for a node,
+    /// visits all children of the node and replaces them by the results.
+    ///
+    /// :returns: the node
+    fn generic_visit(slf: &PyCell<Self>, node: Py<Node>) -> PyResult<Py<Node>> {
+        let py = slf.py();
+        let children = node.borrow(py).children.clone();
+        let mut new_children = Vec::with_capacity(children.len());
+        for child in children {
+            let result = slf.call_method1("visit", (child,))?;
+            if result.is_none() {
+                continue;
+            }
+            match result.extract::<Transformed>() {
+                Ok(Transformed::Single(new_child)) => new_children.push(new_child),
+                Ok(Transformed::Multiple(nodes)) => new_children.extend(nodes),
+                Err(_) => {
+                    return Err(pyo3::exceptions::PyTypeError::new_err(format!(
+                        "visitor must return None, a Node or a list of Nodes, not {}",
+                        result.get_type().name()?
+                    )))
+                }
+            }
+        }
+        Node::replace_children(py, &node, new_children)?;
+        Ok(node)
+    }
+}
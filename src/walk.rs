@@ -0,0 +1,228 @@
+//! Lazy traversal and searching of the Node tree.
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+use crate::nodes::Node;
+
+/// Criteria that a node must satisfy to be returned by `Node.find`
+pub struct NodeFilter {
+    pub name: Option<String>,
+    pub attrs: Option<HashMap<String, String>>,
+    pub meta: Option<HashMap<String, PyObject>>,
+    pub predicate: Option<PyObject>,
+}
+
+impl NodeFilter {
+    fn matches(&self, py: Python, node: &Py<Node>) -> PyResult<bool> {
+        {
+            let node_ref = node.borrow(py);
+            if let Some(name) = &self.name {
+                if name != &node_ref.name {
+                    return Ok(false);
+                }
+            }
+            if let Some(attrs) = &self.attrs {
+                for (key, value) in attrs.iter() {
+                    if node_ref.attrs.get(key) != Some(value) {
+                        return Ok(false);
+                    }
+                }
+            }
+            if let Some(meta) = &self.meta {
+                for (key, value) in meta.iter() {
+                    match node_ref.meta.get(key) {
+                        Some(actual) => {
+                            if !actual.as_ref(py).eq(value)? {
+                                return Ok(false);
+                            }
+                        }
+                        None => return Ok(false),
+                    }
+                }
+            }
+        }
+        // the node must not be borrowed here, since the predicate may mutate it
+        if let Some(predicate) = &self.predicate {
+            if !predicate.call1(py, (node.clone_ref(py),))?.is_true(py)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Return the descendants of `root` satisfying the filter (in depth-first order),
+    /// or only the first, if `first_only` is set.
+    pub fn find(
+        &self,
+        py: Python,
+        root: &Py<Node>,
+        max_depth: Option<usize>,
+        first_only: bool,
+    ) -> PyResult<Vec<Py<Node>>> {
+        fn walk_recursive(
+            py: Python,
+            filter: &NodeFilter,
+            node: &Py<Node>,
+            depth: usize,
+            max_depth: Option<usize>,
+            first_only: bool,
+            results: &mut Vec<Py<Node>>,
+        ) -> PyResult<()> {
+            if max_depth.is_some_and(|max_depth| depth > max_depth) {
+                return Ok(());
+            }
+            let children = node.borrow(py).children.clone();
+            for child in children.iter() {
+                if filter.matches(py, child)? {
+                    results.push(child.clone_ref(py));
+                    if first_only {
+                        return Ok(());
+                    }
+                }
+                stacker::maybe_grow(64 * 1024, 1024 * 1024, || {
+                    walk_recursive(py, filter, child, depth + 1, max_depth, first_only, results)
+                })?;
+                if first_only && !results.is_empty() {
+                    return Ok(());
+                }
+            }
+            Ok(())
+        }
+
+        let mut results = Vec::new();
+        walk_recursive(py, self, root, 1, max_depth, first_only, &mut results)?;
+        Ok(results)
+    }
+}
+
+/// Lazy iterator over the nodes of a tree.
+#[pyclass]
+pub struct NodeWalker {
+    /// Stack of nodes being iterated, and the index of their next child
+    stack: Vec<(Py<Node>, usize)>,
+    /// The node to yield first (in pre-order only)
+    start: Option<Py<Node>>,
+    /// The last node that was yielded (in pre-order only)
+    last: Option<Py<Node>>,
+    /// Whether to skip the children of the last node yielded
+    skip: bool,
+    post_order: bool,
+    include_self: bool,
+}
+
+impl NodeWalker {
+    pub fn new(py: Python, root: &Py<Node>, include_self: bool, order: &str) -> PyResult<Self> {
+        let post_order = match order {
+            "pre" => false,
+            "post" => true,
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Unknown order: {} (must be 'pre' or 'post')",
+                    order
+                )))
+            }
+        };
+        let mut walker = Self {
+            stack: Vec::new(),
+            start: None,
+            last: None,
+            skip: false,
+            post_order,
+            include_self,
+        };
+        if !post_order && include_self {
+            walker.start = Some(root.clone_ref(py));
+        } else {
+            walker.stack.push((root.clone_ref(py), 0));
+        }
+        Ok(walker)
+    }
+
+    fn next_pre_order(&mut self, py: Python) -> Option<Py<Node>> {
+        if let Some(start) = self.start.take() {
+            self.last = Some(start.clone_ref(py));
+            return Some(start);
+        }
+        if let Some(last) = self.last.take() {
+            if !self.skip {
+                self.stack.push((last, 0));
+            }
+            self.skip = false;
+        }
+        while let Some((node, index)) = self.stack.last_mut() {
+            let child = node
+                .borrow(py)
+                .children
+                .get(*index)
+                .map(|c| c.clone_ref(py));
+            match child {
+                Some(child) => {
+                    *index += 1;
+                    self.last = Some(child.clone_ref(py));
+                    return Some(child);
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+        None
+    }
+
+    fn next_post_order(&mut self, py: Python) -> Option<Py<Node>> {
+        while let Some((node, index)) = self.stack.last_mut() {
+            let child = node
+                .borrow(py)
+                .children
+                .get(*index)
+                .map(|c| c.clone_ref(py));
+            match child {
+                Some(child) => {
+                    *index += 1;
+                    self.stack.push((child, 0));
+                }
+                None => {
+                    let (node, _) = self.stack.pop().unwrap();
+                    if self.stack.is_empty() && !self.include_self {
+                        return None;
+                    }
+                    return Some(node);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[pymethods]
+impl NodeWalker {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> Option<Py<Node>> {
+        if self.post_order {
+            self.next_post_order(py)
+        } else {
+            self.next_pre_order(py)
+        }
+    }
+
+    /// Do not descend into the children of the node that was last yielded.
+    ///
+    /// This is only available for pre-order traversal.
+    fn skip_children(&mut self) -> PyResult<()> {
+        if self.post_order {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "Cannot skip children in post-order traversal",
+            ));
+        }
+        if self.last.is_none() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "No node has been yielded",
+            ));
+        }
+        self.skip = true;
+        Ok(())
+    }
+}
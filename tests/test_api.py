@@ -1,4 +1,32 @@
-from markdown_it_pyrs import MarkdownIt, Node
+import copy
+import os
+import pickle
+import subprocess
+import sys
+from typing import Optional
+
+from markdown_it_pyrs import (
+    Abbreviation,
+    AbbreviationDefinition,
+    Alert,
+    Container,
+    Emoji,
+    Fence,
+    Heading,
+    Inserted,
+    Link,
+    Mark,
+    MarkdownIt,
+    Node,
+    NodeTransformer,
+    NodeVisitor,
+    Subscript,
+    Superscript,
+    TableOfContents,
+    Wikilink,
+    diff_trees,
+    node_schema,
+)
 import pytest
 
 
@@ -79,3 +107,689 @@ def test_tree_pretty() -> None:
       content: markdown-it rulezz!
 """
     )
+
+
+def test_node_parent() -> None:
+    mdit = MarkdownIt()
+    root = mdit.tree("- a *b*")
+    assert root.parent is None
+    for node in root.walk(include_self=False):
+        assert node.parent is not None
+        assert node in node.parent.children
+
+
+def test_node_append_insert() -> None:
+    node = Node("root")
+    a, b, c = Node("a"), Node("b"), Node("c")
+    node.append(a)
+    node.append(c)
+    node.insert(1, b)
+    assert [n.name for n in node.children] == ["a", "b", "c"]
+    assert all(n.parent is node for n in node.children)
+    node.insert(-1, Node("d"))
+    assert [n.name for n in node.children] == ["a", "b", "d", "c"]
+    # appending a node that already has a parent moves it
+    other = Node("other")
+    other.append(a)
+    assert [n.name for n in node.children] == ["b", "d", "c"]
+    assert a.parent is other
+    with pytest.raises(ValueError):
+        a.append(other)
+
+
+def test_node_remove() -> None:
+    mdit = MarkdownIt()
+    root = mdit.tree("- a *b*")
+    em = root.walk()[4]
+    assert em.name == "em"
+    em.remove()
+    assert em.parent is None
+    assert [n.name for n in root.walk()] == ["root", "bullet_list", "list_item", "text"]
+    with pytest.raises(ValueError):
+        em.remove()
+
+
+def test_node_replace_with() -> None:
+    node = Node("root")
+    a, b = Node("a"), Node("b")
+    node.children = [a, b]
+    a.replace_with([Node("x"), Node("y")])
+    assert [n.name for n in node.children] == ["x", "y", "b"]
+    assert a.parent is None
+    b.replace_with(Node("z"))
+    assert [n.name for n in node.children] == ["x", "y", "z"]
+
+
+def test_node_wrap_unwrap() -> None:
+    mdit = MarkdownIt()
+    root = mdit.tree("*a* b")
+    em = root.children[0].children[0]
+    wrapper = em.wrap("strong")
+    assert wrapper.srcmap == em.srcmap
+    assert em.parent is wrapper
+    assert [n.name for n in root.walk()] == [
+        "root",
+        "paragraph",
+        "strong",
+        "em",
+        "text",
+        "text",
+    ]
+    em.unwrap()
+    assert [n.name for n in root.walk()] == ["root", "paragraph", "strong", "text", "text"]
+    assert wrapper.children[0].parent is wrapper
+
+
+def test_node_select() -> None:
+    mdit = MarkdownIt("gfm")
+    root = mdit.tree("# a\n\n## b *c*\n\n- [x] d\n\n[e](http://x.com 'f')")
+    assert [n.meta["content"] for n in root.select("heading[level=2] > text")] == ["b "]
+    assert [n.meta["content"] for n in root.select("heading[level=2] text")] == ["b ", "c"]
+    assert [n.name for n in root.select("em, link[title='f']")] == ["em", "link"]
+    assert [n.name for n in root.select("list_item > *")] == ["todo_checkbox", "text"]
+    assert len(root.select("todo_checkbox[checked=true]")) == 1
+    assert len(root.select("link[url^=http]")) == 1
+    assert len(root.select("*[level]")) == 2
+    assert root.select("root") == []
+    first = root.select_one("text")
+    assert first is not None
+    assert first.meta["content"] == "a"
+    assert root.select_one("table") is None
+    with pytest.raises(ValueError):
+        root.select("heading >")
+
+
+def test_node_find() -> None:
+    mdit = MarkdownIt()
+    root = mdit.tree("# a\n\n## b *c*\n\n[d](e)")
+    heading = root.find("heading", meta={"level": 2})
+    assert heading is not None
+    assert heading.srcmap == (5, 13)
+    assert root.find("em", max_depth=1) is None
+    assert root.find("em", max_depth=2) is not None
+    assert root.find(attrs={"missing": "x"}) is None
+    assert [n.meta["content"] for n in root.find_all("text")] == ["a", "b ", "c", "d"]
+    assert [n.name for n in root.find_all(max_depth=1)] == ["heading", "heading", "paragraph"]
+    visited = []
+
+    def _predicate(node: Node) -> bool:
+        visited.append(node.name)
+        return node.name == "heading"
+
+    assert root.find(predicate=_predicate) is not None
+    assert visited == ["heading"]
+
+
+def test_node_iter_walk() -> None:
+    mdit = MarkdownIt()
+    root = mdit.tree("- a *b*\n\nc")
+    walker = root.iter_walk()
+    assert [n.name for n in walker] == [n.name for n in root.walk()]
+    assert [n.name for n in root.iter_walk(include_self=False, order="post")] == [
+        "text",
+        "text",
+        "em",
+        "list_item",
+        "bullet_list",
+        "text",
+        "paragraph",
+    ]
+    names = []
+    walker = root.iter_walk()
+    for node in walker:
+        names.append(node.name)
+        if node.name == "bullet_list":
+            walker.skip_children()
+    assert names == ["root", "bullet_list", "paragraph", "text"]
+    with pytest.raises(ValueError):
+        root.iter_walk(order="post").skip_children()
+    with pytest.raises(ValueError):
+        root.iter_walk(order="other")
+
+
+def test_events() -> None:
+    mdit = MarkdownIt()
+    events = [
+        (event, value if isinstance(value, str) else value.name)
+        for event, value in mdit.events("# a *b*\n\n- c")
+    ]
+    assert events == [
+        ("enter", "root"),
+        ("enter", "heading"),
+        ("text", "a "),
+        ("enter", "em"),
+        ("text", "b"),
+        ("exit", "em"),
+        ("exit", "heading"),
+        ("enter", "bullet_list"),
+        ("enter", "list_item"),
+        ("text", "c"),
+        ("exit", "list_item"),
+        ("exit", "bullet_list"),
+        ("exit", "root"),
+    ]
+    # the same node is returned on enter and exit
+    stream = mdit.events("# a")
+    _, root = next(stream)
+    _, heading = next(stream)
+    assert heading.meta == {"level": 1}
+    assert heading.children == []
+    assert list(stream)[-2:] == [("exit", heading), ("exit", root)]
+
+
+def test_node_visitor() -> None:
+    class _Visitor(NodeVisitor):
+        def __init__(self) -> None:
+            self.headings: list = []
+            self.texts = 0
+
+        def visit_heading(self, node: Node) -> str:
+            self.headings.append(node.meta["level"])
+            self.generic_visit(node)
+            return "heading"
+
+        def visit_text(self, node: Node) -> None:
+            self.texts += 1
+
+    mdit = MarkdownIt()
+    root = mdit.tree("# a\n\n## b *c*\n\nd")
+    visitor = _Visitor()
+    assert visitor.visit(root) is None
+    assert visitor.headings == [1, 2]
+    assert visitor.texts == 4
+    assert visitor.visit(root.children[0]) == "heading"
+
+
+def test_node_transformer() -> None:
+    class _Transformer(NodeTransformer):
+        def visit_em(self, node: Node) -> Node:
+            strong = Node("strong")
+            strong.children = node.children
+            return strong
+
+        def visit_softbreak(self, node: Node) -> None:
+            return None
+
+        def visit_heading(self, node: Node) -> list:
+            return [Node("hr"), *node.children]
+
+    mdit = MarkdownIt()
+    root = mdit.tree("# a\n\nb\n*c*")
+    assert _Transformer().visit(root) is root
+    assert [n.name for n in root.walk()] == [
+        "root",
+        "hr",
+        "text",
+        "paragraph",
+        "text",
+        "strong",
+        "text",
+    ]
+    assert all(child.parent is root for child in root.children)
+
+    class _Invalid(NodeTransformer):
+        def visit_text(self, node: Node) -> int:
+            return 1
+
+    with pytest.raises(TypeError):
+        _Invalid().visit(root)
+
+
+def test_node_equality() -> None:
+    mdit = MarkdownIt("gfm")
+    src = "# a *b*\n\n| c |\n| - |\n| d |"
+    assert mdit.tree(src) == mdit.tree(src)
+    assert mdit.tree(src) != mdit.tree(src + "e")
+    assert mdit.tree("# a") != mdit.tree("## a")
+    assert mdit.tree("a") != mdit.tree("\na")
+    assert mdit.tree("a").equals(mdit.tree("\na"), srcmap=False)
+    assert Node("a") != "a"
+    # equal nodes must hash equally, so mutable nodes are unhashable
+    with pytest.raises(TypeError):
+        hash(Node("a"))
+    with pytest.raises(TypeError):
+        {mdit.tree(src)}
+
+
+def test_node_copy() -> None:
+    mdit = MarkdownIt("gfm")
+    root = mdit.tree("| a |\n| - |\n| b |")
+    # a node can only have one parent, so copies never share children
+    shallow = copy.copy(root)
+    assert shallow == root
+    assert shallow.children[0] is not root.children[0]
+    assert shallow.children[0].parent is shallow
+    deep = copy.deepcopy(root)
+    assert deep == root
+    assert deep.parent is None
+    assert deep.children[0] is not root.children[0]
+    assert deep.children[0].parent is deep
+    assert deep.children[0].meta["alignments"] is not root.children[0].meta["alignments"]
+    deep.children[0].name = "other"
+    assert root.children[0].name == "table"
+    shallow.children[0].remove()
+    assert len(root.children) == 1
+    assert len(shallow.children) == 0
+
+
+def test_diff_trees() -> None:
+    mdit = MarkdownIt()
+    old = mdit.tree("# Intro\n\nSee [docs](a)\n\nPara one\n\nPara two")
+    new = mdit.tree("# Introduction\n\nPara two\n\nSee [docs](b)\n\nPara one\n\nNew")
+    assert diff_trees(old, old) == []
+    edits = diff_trees(old, new)
+    assert [(e.op, e.path_a, e.path_b) for e in edits] == [
+        ("update", [0, 0], [0, 0]),
+        ("update", [1, 1], [2, 1]),
+        ("move", [3], [1]),
+        ("insert", None, [4]),
+    ]
+    assert edits[0].changes == {"meta.content": ("Intro", "Introduction")}
+    assert edits[1].changes == {"meta.url": ("a", "b")}
+    assert edits[2].srcmap_a == (34, 42)
+    assert edits[3].node_a is None
+    assert edits[3].node_b is new.children[4]
+
+
+def test_render_diff() -> None:
+    mdit = MarkdownIt("gfm")
+    assert mdit.render_diff("a *b*", "a *b*") == mdit.render("a *b*")
+    assert mdit.render_diff("# Intro\n\nSee [docs](a) now", "# Intro\n\nSee [docs](b) later") == (
+        "<h1>Intro</h1>\n"
+        '<p>See <del><a href="a">docs</a></del><ins><a href="b">docs</a></ins> '
+        "<del>now</del><ins>later</ins></p>\n"
+    )
+    assert mdit.render_diff("a\n\nb", "a\n\n---") == (
+        "<p>a</p>\n<del>\n<p>b</p>\n</del>\n<ins>\n<hr />\n</ins>\n"
+    )
+    # list items are not wrapped, since only <li> is allowed in <ul>
+    assert mdit.render_diff("- x", "- x\n- y", xhtml=False) == (
+        "<ul>\n<li>x</li>\n<li><ins>y</ins></li>\n</ul>\n"
+    )
+
+
+def test_render_text() -> None:
+    mdit = MarkdownIt("gfm")
+    src = (
+        "# Title *here*\n\n"
+        "See ![alt](x.png) and [link](http://x) with `code`<br>.\n\n"
+        "> quote\n>\n> - a\n>   b\n\n"
+        "3. [x] done\n4. todo\n\n"
+        "| a | b |\n|---|---|\n| c | d |\n\n"
+        "```\nprint(1)\n```\n\n"
+        "---\n"
+    )
+    assert mdit.render_text(src) == (
+        "Title here\n\n"
+        "See alt and link with code.\n\n"
+        "> quote\n>\n> - a\n>   b\n\n"
+        "3. [x] done\n4. todo\n\n"
+        "a | b\nc | d\n\n"
+        "print(1)\n\n"
+        "---\n"
+    )
+    assert mdit.render_text("") == ""
+    root = mdit.tree("# a *b*\n\nc\nd `e`\n\n<span>f</span>")
+    assert root.children[0].text_content() == "a b"
+    assert root.children[1].text_content() == "c\nd e"
+    assert root.children[2].text_content() == "f"
+
+
+def test_prose() -> None:
+    mdit = MarkdownIt("gfm")
+    src = "# Héllo *wörld*\n\n> - \\*teh\\* &amp; [linkk](x) `code`\n\n```\nprint(1)\n```\n"
+    prose = mdit.prose(src)
+    assert prose.text == "Héllo wörld\n\n*teh* & linkk \n"
+    assert prose.source == src
+    assert prose.segments()[:2] == [(0, 6, 2, 8), (6, 11, 9, 14)]
+    assert prose.segments(bytes=True)[:2] == [(0, 6, 2, 9), (6, 11, 10, 16)]
+    for word in ["wörld", "teh", "&", "linkk"]:
+        start = prose.text.index(word)
+        src_start, src_end = prose.source_span(start, start + len(word))
+        assert src[src_start:src_end] == word
+    assert prose.source_span(0, 5, bytes=True) == (2, 8)
+    with pytest.raises(ValueError):
+        prose.source_span(5, 1000)
+    prose = mdit.prose(src, code=True)
+    start = prose.text.index("print(1)")
+    assert prose.source_span(start, start + 8) == (src.index("print(1)"), src.index("print(1)") + 8)
+    # each line of code maps to its own line of the source
+    for src in [
+        "```\nfoo bar\n\nbaz\n```\n",
+        "    foo bar\n\n    baz\n",
+        "> ```\n> foo bar\n>\n> baz\n",
+    ]:
+        prose = mdit.prose(src, code=True)
+        assert prose.text == "foo bar\n\nbaz\n"
+        for word in ["foo bar", "baz"]:
+            start = prose.text.index(word)
+            src_start, src_end = prose.source_span(start, start + len(word))
+            assert src[src_start:src_end] == word
+        assert len(prose.segments()) == 2
+
+
+def test_pickle_markdown_it() -> None:
+    mdit = MarkdownIt("zero").enable_many(["heading", "fence", "table"])
+    mdit._unset_lang_prefix()
+    src = "# a\n\n| b |\n| - |\n| c |\n\n```py\nd\n```\n\n*e*"
+    unpickled = pickle.loads(pickle.dumps(mdit))
+    assert isinstance(unpickled, MarkdownIt)
+    assert unpickled.render(src) == mdit.render(src)
+    assert unpickled._debug() == mdit._debug()
+
+
+def test_pickle_node() -> None:
+    mdit = MarkdownIt("gfm")
+    root = mdit.tree("# a *b*\n\n| c |\n| - |\n| d |")
+    root.children[0].attrs = {"id": "x"}
+    unpickled = pickle.loads(pickle.dumps(root))
+    assert unpickled == root
+    assert unpickled.children[0].attrs == {"id": "x"}
+    assert unpickled.children[0].parent is unpickled
+    assert unpickled.children[0]._rust_path == root.children[0]._rust_path
+    child = pickle.loads(pickle.dumps(root.children[1]))
+    assert child == root.children[1]
+    assert child.parent is None
+
+
+def test_node_bytes() -> None:
+    mdit = MarkdownIt("gfm")
+    root = mdit.tree("# a *b*\n\n| c |\n| - |\n| d |\n\n- [x] e")
+    root.children[0].attrs = {"id": "x"}
+    meta = {"list": [1, -5, 2**70, 1.5, None], "tuple": (True, b"z"), "dict": {1: "a"}}
+    root.children[1].meta = meta
+    data = root.to_bytes()
+    assert isinstance(data, bytes)
+    loaded = Node.from_bytes(data)
+    assert loaded == root
+    assert loaded.children[1].meta == meta
+    assert loaded.children[0].parent is loaded
+    assert loaded.children[0]._rust_path == root.children[0]._rust_path
+    for invalid in [b"", data[:-1], data + b"x"]:
+        with pytest.raises(ValueError):
+            Node.from_bytes(invalid)
+    root.meta = {"x": object()}
+    with pytest.raises(TypeError):
+        root.to_bytes()
+    # deeply nested or recursive meta values are errors, not crashes
+    nested = b"MDIT\x01\x02a\x00\x00\x00\x01\x02m" + b"\x08\x01" * 100_000
+    with pytest.raises(ValueError, match="nested too deeply"):
+        Node.from_bytes(nested)
+    recursive: list = []
+    recursive.append(recursive)
+    root.meta = {"x": recursive}
+    with pytest.raises(ValueError, match="recursive"):
+        root.to_bytes()
+
+
+def test_typed_nodes() -> None:
+    mdit = MarkdownIt("gfm").enable("tasklist")
+    root = mdit.tree('# a\n\n[b](c "d")\n\n```py\ne\n```\n\n| f |\n| -: |\n\n- [x] g')
+    heading = root.children[0]
+    assert isinstance(heading, Heading) and isinstance(heading, Node)
+    assert type(root.children[1]).__name__ == "Paragraph"
+    assert heading.level == heading.meta["level"] == 1
+    heading.level = 2
+    assert heading.meta["level"] == 2
+    with pytest.raises(TypeError):
+        heading.level = "x"  # type: ignore[assignment]
+    link = root.find("link")
+    assert isinstance(link, Link)
+    assert (link.url, link.title) == ("c", "d")
+    link.title = None
+    assert link.title is None and "title" not in link.meta
+    fence = root.find("fence")
+    assert isinstance(fence, Fence) and fence.info == "py" and fence.content == "e\n"
+    assert root.find("table").alignments == ["right"]  # type: ignore[attr-defined]
+    assert root.find("todo_checkbox").checked is True  # type: ignore[attr-defined]
+    # the class is preserved when copying and serializing
+    assert type(copy.copy(heading)) is Heading
+    assert type(copy.deepcopy(root).children[0]) is Heading
+    assert type(pickle.loads(pickle.dumps(heading))) is Heading
+    assert type(Node.from_bytes(heading.to_bytes())) is Heading
+    # typed nodes can be created directly
+    assert Heading().name == "heading"
+    with pytest.raises(AttributeError):
+        Heading().level
+
+
+def test_node_schema() -> None:
+    schema = node_schema()
+    assert schema["heading"] == {
+        "plugins": ["heading"],
+        "_rust_path": "markdown_it::plugins::cmark::block::heading::ATXHeading",
+        "meta": {"level": {"type": "int", "optional": False}},
+    }
+    assert schema["root"]["plugins"] == []
+    assert set(schema["text_special"]["plugins"]) == {"entity", "escape", "autolink_ext"}
+    plugins = set(MarkdownIt.list_plugins())
+    for entry in schema.values():
+        assert set(entry["plugins"]) <= plugins
+    # the schema matches the nodes of a tree
+    mdit = MarkdownIt("gfm").enable_many(["footnote", "tasklist", "deflist"])
+    root = mdit.tree("# a\n\n[b](c) x[^1] ~~y~~\n\n[^1]: z\n\n- [x] d\n\ne\n: f\n")
+    for node in [root, *root.walk()]:
+        entry = schema[node.name]
+        assert node._rust_path == entry["_rust_path"]
+        assert set(node.meta) <= set(entry["meta"])
+        for key, field in entry["meta"].items():
+            assert field["optional"] or key in node.meta
+
+
+def test_math() -> None:
+    mdit = MarkdownIt().enable("dollarmath").enable("amsmath")
+    root = mdit.tree("$a$\n\n$$\nb\n$$ (c)\n\n\\begin{align}\nd\n\\end{align}\n")
+    inline, block, ams = root.find("math_inline"), *root.find_all("math_block")
+    assert (inline.content, inline.display) == ("a", False)  # type: ignore[union-attr]
+    assert block.meta == {"content": "b", "label": "c"}
+    assert ams.meta == {"content": "\\begin{align}\nd\n\\end{align}", "environment": "align"}
+    assert mdit.render_text("x $a$") == "x a\n"
+    # options
+    mdit = MarkdownIt().enable(
+        "dollarmath", allow_digits=False, double_inline=True, display_class="eq"
+    )
+    assert mdit.render("$1 and $2") == "<p>$1 and $2</p>\n"
+    assert mdit.render("a $$b$$") == '<p>a <span class="eq">b</span></p>\n'
+    mdit = MarkdownIt().enable("dollarmath", inline_class="", passthrough=True)
+    assert mdit.render("$<math></math>$") == "<p><math></math></p>\n"
+    assert pickle.loads(pickle.dumps(mdit)).render("$<i>$") == "<p><i></p>\n"
+    with pytest.raises(ValueError, match="Unknown option"):
+        MarkdownIt().enable("dollarmath", other=True)
+    with pytest.raises(TypeError, match="Invalid option"):
+        MarkdownIt().enable("dollarmath", allow_space="yes")
+    with pytest.raises(ValueError, match="Unknown option"):
+        MarkdownIt().enable("table", other=True)
+
+
+def test_container() -> None:
+    mdit = MarkdownIt().enable("container")
+    root = mdit.tree(":::: warning Here be dragons\n::: note\n*content*\n:::\n::::\n")
+    outer = root.children[0]
+    assert isinstance(outer, Container)
+    assert outer.meta == {"name": "warning", "info": "Here be dragons", "marker_len": 4}
+    assert outer.name == "container"
+    assert outer.container_name == "warning"
+    assert outer.children[0].meta == {"name": "note", "info": "", "marker_len": 3}
+    assert mdit.render_text("::: note\ncontent\n:::\n\nafter") == "content\n\nafter\n"
+
+
+def test_alerts() -> None:
+    root = MarkdownIt("gfm").tree("> [!WARNING]\n> Be *careful*\n")
+    alert = root.children[0]
+    assert isinstance(alert, Alert)
+    assert alert.kind == "warning"
+    assert [n.name for n in alert.walk()] == ["alert", "paragraph", "text", "em", "text"]
+    assert alert.children[0].srcmap == (15, 27)
+    assert MarkdownIt().tree("> [!WARNING]\n> x\n").children[0].name == "blockquote"
+
+
+def test_attrs() -> None:
+    mdit = MarkdownIt().enable("attrs")
+    root = mdit.tree("# Title {#top .a .b}\n\n[link](url){target=_blank}\n")
+    heading, paragraph = root.children
+    assert heading.attrs == {"id": "top", "class": "a b"}
+    assert heading.children[0].meta == {"content": "Title"}
+    assert heading.children[0].srcmap == (2, 7)
+    assert paragraph.children[0].attrs == {"target": "_blank"}
+    # explicit ids take precedence over generated ones
+    mdit = MarkdownIt().enable("heading_anchors").enable("attrs")
+    anchor = mdit.tree("# Title {#top}").children[0].children[0]
+    assert anchor.meta == {"href": "top"}
+    mdit = MarkdownIt().enable("attrs", allowed_attributes=["class"])
+    assert mdit.render("[a](b){.x #y}") == '<p><a class="x" href="b">a</a></p>\n'
+    with pytest.raises(TypeError, match="Invalid option"):
+        MarkdownIt().enable("attrs", allowed_attributes=1)
+
+
+def test_attrs_name_limit() -> None:
+    # attribute names are kept for the life of the process, so test in a new process
+    code = (
+        "from markdown_it_pyrs import MarkdownIt\n"
+        "mdit = MarkdownIt().enable('attrs')\n"
+        "mdit.render(''.join(f'*a*{{a{i}=x}}\\n' for i in range(1100)))\n"
+        "print(mdit.render('*b*{new=x} *c*{class=y}'), end='')\n"
+    )
+    env = {**os.environ, "PYTHONPATH": os.pathsep.join(sys.path)}
+    result = subprocess.run(
+        [sys.executable, "-c", code], capture_output=True, text=True, check=True, env=env
+    )
+    assert result.stdout == '<p><em>b</em> <em class="y">c</em></p>\n'
+
+
+def test_wikilinks() -> None:
+    mdit = MarkdownIt().enable("wikilinks", base_url="/wiki/", suffix=".html")
+    link = mdit.tree("[[My Page#Intro|intro]]").children[0].children[0]
+    assert isinstance(link, Wikilink)
+    assert link.meta == {
+        "target": "My Page",
+        "anchor": "Intro",
+        "label": "intro",
+        "url": "/wiki/My%20Page.html#Intro",
+    }
+    assert link.children[0].meta == {"content": "intro"}
+    assert pickle.loads(pickle.dumps(mdit)).render("[[Page]]") == (
+        '<p><a class="wikilink" href="/wiki/Page.html">Page</a></p>\n'
+    )
+
+    def resolver(target: str, anchor: Optional[str]) -> Optional[str]:
+        return None if target == "Other" else f"/{target.lower()}"
+
+    mdit = MarkdownIt().enable("wikilinks", resolver=resolver)
+    assert mdit.render("[[Page]] [[Other]]") == (
+        '<p><a class="wikilink" href="/page">Page</a> '
+        '<a class="wikilink" href="Other">Other</a></p>\n'
+    )
+
+    # unsafe URLs from the resolver are not linked either
+    mdit = MarkdownIt().enable("wikilinks", resolver=lambda target, anchor: target)
+    assert mdit.render("a [[javascript:alert(1)|x]] [[data:text/html,x]] [[a b]]") == (
+        "<p>a [[javascript:alert(1)|x]] [[data:text/html,x]] "
+        '<a class="wikilink" href="a%20b">a b</a></p>\n'
+    )
+
+    def failing(target: str, anchor: Optional[str]) -> str:
+        raise KeyError(target)
+
+    with pytest.raises(KeyError, match="Page"):
+        MarkdownIt().enable("wikilinks", resolver=failing).render("[[Page]]")
+    with pytest.raises(TypeError, match="must be callable"):
+        MarkdownIt().enable("wikilinks", resolver="/wiki/")
+
+
+def test_emoji() -> None:
+    emoji = MarkdownIt("gfm").tree("hi :wave:").children[0].children[1]
+    assert isinstance(emoji, Emoji)
+    assert emoji.meta == {"markup": "wave", "content": "\U0001f44b"}
+    assert emoji.srcmap == (3, 9)
+    mdit = MarkdownIt().enable(
+        "emoji",
+        shortcodes={"shipit": "", "smile": ":)"},
+        image_url="/emoji/{shortcode}.png",
+    )
+    assert mdit.render(":shipit: :smile:") == (
+        '<p><img class="emoji" src="/emoji/shipit.png" alt=":shipit:" title=":shipit:" /> '
+        '<img class="emoji" src="/emoji/smile.png" alt=":)" title=":smile:" /></p>\n'
+    )
+    assert mdit.render_text(":shipit: :smile:") == ":shipit: :)\n"
+    mdit = MarkdownIt().enable("emoji", image_url="{codepoints}.svg")
+    assert mdit.render(":tada:") == (
+        '<p><img class="emoji" src="1f389.svg" alt="\U0001f389" title=":tada:" /></p>\n'
+    )
+    with pytest.raises(ValueError, match="Invalid emoji shortcode"):
+        MarkdownIt().enable("emoji", shortcodes={"a b": "x"})
+
+
+def test_pairs() -> None:
+    mdit = MarkdownIt().enable_many(["strikethrough", "sub", "sup", "mark", "ins"])
+    para = mdit.tree("H~2~O x^2^ ==a== ++b++").children[0]
+    sub, sup, mark, ins = (para.children[i] for i in (1, 3, 5, 7))
+    assert isinstance(sub, Subscript) and sub.marker == "~"
+    assert isinstance(sup, Superscript) and sup.marker == "^"
+    assert isinstance(mark, Mark) and mark.marker == "="
+    assert isinstance(ins, Inserted) and ins.marker == "+"
+    assert sub.srcmap == (1, 4)
+    assert sub.children[0].srcmap == (2, 3)
+    # double tildes are left to strikethrough, whichever is enabled first
+    for plugins in (["sub", "strikethrough"], ["strikethrough", "sub"]):
+        mdit = MarkdownIt().enable_many(plugins)
+        assert mdit.render("~~a ~b~ c~~") == "<p><s>a <sub>b</sub> c</s></p>\n"
+    assert MarkdownIt().enable("sub").render("~a b~ ~~c~~") == "<p>~a b~ ~~c~~</p>\n"
+
+
+def test_abbr() -> None:
+    mdit = MarkdownIt().enable("abbr")
+    tree = mdit.tree("*[HTML]: Hyper Text Markup Language\n\nan HTML page\n")
+    definition, paragraph = tree.children
+    assert isinstance(definition, AbbreviationDefinition)
+    assert definition.meta == {"label": "HTML", "title": "Hyper Text Markup Language"}
+    abbr = paragraph.children[1]
+    assert isinstance(abbr, Abbreviation)
+    assert abbr.title == "Hyper Text Markup Language"
+    assert abbr.srcmap == (40, 44)
+    assert abbr.children[0].meta == {"content": "HTML"}
+    assert mdit.render_text("*[HTML]: Hyper Text Markup Language\n\nan HTML page\n") == (
+        "an HTML page\n"
+    )
+
+
+def test_toc() -> None:
+    src = "[[toc]]\n\n# A {#first}\n\n## B\n\n### C\n"
+    mdit = MarkdownIt().enable_many(["attrs", "wikilinks", "heading_anchors"])
+    mdit.enable("toc", max_level=2, list_type="ol")
+    toc = mdit.tree(src).children[0]
+    assert isinstance(toc, TableOfContents)
+    assert toc.srcmap == (0, 7)
+    assert mdit.render(src).startswith(
+        '<nav class="table-of-contents">\n<ol>\n<li><a href="#first">A</a>\n'
+        '<ol>\n<li><a href="#b">B</a></li>\n</ol>\n</li>\n</ol>\n</nav>\n'
+    )
+    with pytest.raises(ValueError, match="Invalid heading levels"):
+        MarkdownIt().enable("toc", min_level=3, max_level=2)
+    with pytest.raises(ValueError, match="Invalid option 'list_type'"):
+        MarkdownIt().enable("toc", list_type="dl")
+
+
+def test_cache() -> None:
+    mdit = MarkdownIt("zero", cache_size=2)
+    assert mdit.render("# a") == mdit.render("# a") == "# a\n"
+    assert mdit.cache_info() == {"hits": 1, "misses": 1, "maxsize": 2, "currsize": 1}
+    assert mdit.render("# a", xhtml=False) == "# a\n"
+    assert mdit.cache_info()["misses"] == 2
+    # trees are rebuilt from the cache, so modifying them does not affect the cache
+    mdit.tree("b").children[0].name = "other"
+    assert mdit.tree("b").children[0].name == "text"
+    assert mdit.cache_info() == {"hits": 2, "misses": 3, "maxsize": 2, "currsize": 2}
+    gfm = MarkdownIt("gfm", cache_size=1)
+    tree = gfm.tree("# a\n\n| b |\n| - |")
+    cached = gfm.tree("# a\n\n| b |\n| - |")
+    assert cached == tree and cached is not tree
+    assert [type(n) for n in cached.walk()] == [type(n) for n in tree.walk()]
+    assert cached.children[1].parent is cached
+    # changing the configuration clears the cache
+    mdit.enable("heading")
+    assert mdit.cache_info()["currsize"] == 0
+    assert mdit.render("# a") == "<h1>a</h1>\n"
+    assert pickle.loads(pickle.dumps(mdit)).cache_info()["maxsize"] == 2
+    mdit.cache_clear()
+    assert mdit.cache_info() == {"hits": 0, "misses": 0, "maxsize": 2, "currsize": 0}
+    assert MarkdownIt().cache_info()["maxsize"] == 0
@@ -113,6 +113,66 @@ def test_deflist(file_params):
     assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
 
 
+@pytest.mark.param_file(FIXTURE_PATH.joinpath("dollarmath.md"))
+def test_dollarmath(file_params):
+    md = MarkdownIt().enable("dollarmath")
+    assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
+
+
+@pytest.mark.param_file(FIXTURE_PATH.joinpath("amsmath.md"))
+def test_amsmath(file_params):
+    md = MarkdownIt().enable("amsmath")
+    assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
+
+
+@pytest.mark.param_file(FIXTURE_PATH.joinpath("container.md"))
+def test_container(file_params):
+    md = MarkdownIt().enable("container")
+    assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
+
+
+@pytest.mark.param_file(FIXTURE_PATH.joinpath("alerts.md"))
+def test_alerts(file_params):
+    md = MarkdownIt().enable("alerts")
+    assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
+
+
+@pytest.mark.param_file(FIXTURE_PATH.joinpath("attrs.md"))
+def test_attrs(file_params):
+    md = MarkdownIt().enable("attrs")
+    assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
+
+
+@pytest.mark.param_file(FIXTURE_PATH.joinpath("wikilinks.md"))
+def test_wikilinks(file_params):
+    md = MarkdownIt().enable("wikilinks")
+    assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
+
+
+@pytest.mark.param_file(FIXTURE_PATH.joinpath("emoji.md"))
+def test_emoji(file_params):
+    md = MarkdownIt().enable("emoji")
+    assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
+
+
+@pytest.mark.param_file(FIXTURE_PATH.joinpath("pairs.md"))
+def test_pairs(file_params):
+    md = MarkdownIt().enable_many(["strikethrough", "sub", "sup", "mark", "ins"])
+    assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
+
+
+@pytest.mark.param_file(FIXTURE_PATH.joinpath("abbr.md"))
+def test_abbr(file_params):
+    md = MarkdownIt().enable("abbr")
+    assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
+
+
+@pytest.mark.param_file(FIXTURE_PATH.joinpath("toc.md"))
+def test_toc(file_params):
+    md = MarkdownIt().enable("toc")
+    assert file_params.assert_expected(md.render(file_params.content), rstrip=True)
+
+
 @pytest.mark.param_file(FIXTURE_PATH.joinpath("ast.md"))
 def test_ast(file_params):
     md = MarkdownIt().enable_many(